@@ -0,0 +1,189 @@
+use clap::{clap_app, App, ArgMatches};
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, Value};
+use crate::output::tree;
+use crate::v2::PagerDutyError;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(config =>
+        (about: "List, change and check the profiles in the config file")
+        (@setting SubcommandRequiredElseHelp)
+        (@subcommand list =>
+            (about: "List every profile, with tokens hidden")
+        )
+        (@subcommand set =>
+            (about: "Change a setting of a profile, creating the profile if needed")
+            (@arg name: +required "Name of the profile.")
+            (@arg key: +required possible_value[token token_command api_url format include exclude depth] "Setting to change.")
+            (@arg value: +required +multiple "New value. `include` and `exclude` take several.")
+        )
+        (@subcommand use_profile =>
+            (name: "use")
+            (about: "Use a profile when --profile isn't given")
+            (@arg name: +required "Name of the profile.")
+        )
+        (@subcommand validate =>
+            (about: "Check the config file for mistakes")
+        )
+    )
+}
+
+pub fn run(path: Option<PathBuf>, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    let path = required_path(path)?;
+
+    match args.subcommand() {
+        ("list", Some(_)) => list(&path),
+        ("set", Some(args)) => set(&path, args),
+        ("use", Some(args)) => use_profile(&path, args),
+        ("validate", Some(_)) => validate(&path),
+        _ => unreachable!(),
+    }
+}
+
+/// The config file, which can only be missing when there's no home directory.
+pub(crate) fn required_path(path: Option<PathBuf>) -> Result<PathBuf, PagerDutyError> {
+    path.ok_or_else(|| PagerDutyError::Config {
+        path: "-".into(),
+        message: "unable to find a home directory, use --config".into(),
+    })
+}
+
+fn list(path: &Path) -> Result<(), PagerDutyError> {
+    let config = Config::load(path)?;
+    if config.profiles.is_empty() {
+        println!("No profiles in {}.", path.display());
+        return Ok(());
+    }
+
+    let tree = tree::TreePrinter::default();
+    for (name, profile) in &config.profiles {
+        let root = if config.default_profile.as_ref() == Some(name) {
+            tree.add_line(format!("Profile - {} (default)", name))
+        } else {
+            tree.add_line(format!("Profile - {}", name))
+        };
+
+        if let Some(token) = &profile.token {
+            root.add_line(format!("token - {}", mask(token)));
+        }
+        if let Some(command) = &profile.token_command {
+            root.add_line(format!("token_command - {}", command));
+        }
+        if let Some(api_url) = &profile.api_url {
+            root.add_line(format!("api_url - {}", api_url));
+        }
+        if let Some(format) = &profile.format {
+            root.add_line(format!("format - {}", format));
+        }
+        if !profile.include.is_empty() {
+            root.add_line(format!("include - {}", profile.include.join(", ")));
+        }
+        if !profile.exclude.is_empty() {
+            root.add_line(format!("exclude - {}", profile.exclude.join(", ")));
+        }
+        if let Some(depth) = profile.depth {
+            root.add_line(format!("depth - {}", depth));
+        }
+    }
+
+    println!("{}", tree.render());
+
+    Ok(())
+}
+
+fn set(path: &Path, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    let mut config = Config::load(path)?;
+    let name = args.value_of("name").unwrap();
+    let key = args.value_of("key").unwrap();
+    let values: Vec<String> = args.values_of("value").unwrap().map(String::from).collect();
+
+    let value = match key {
+        "include" | "exclude" => Value::Array(values),
+        _ if values.len() > 1 => {
+            return Err(invalid(path, format!("{} takes a single value", key)));
+        }
+        "depth" => match values[0].parse() {
+            Ok(depth) => Value::Integer(depth),
+            Err(_) => return Err(invalid(path, "depth must be a number (0-255)".into())),
+        },
+        _ => Value::String(values[0].clone()),
+    };
+
+    config
+        .profiles
+        .entry(name.to_string())
+        .or_default()
+        .set(key, value)
+        .map_err(|message| invalid(path, message))?;
+
+    check(path, &config)?;
+    config.save(path)?;
+    println!("Set {} for profile {} in {}", key, name, path.display());
+
+    Ok(())
+}
+
+fn use_profile(path: &Path, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    let mut config = Config::load(path)?;
+    let name = args.value_of("name").unwrap();
+
+    if !config.profiles.contains_key(name) {
+        return Err(PagerDutyError::NotFound {
+            kind: "profile",
+            name: name.to_string(),
+        });
+    }
+
+    config.default_profile = Some(name.to_string());
+    config.save(path)?;
+    println!("Using profile {} by default", name);
+
+    Ok(())
+}
+
+fn validate(path: &Path) -> Result<(), PagerDutyError> {
+    let config = Config::load(path)?;
+    check(path, &config)?;
+    println!("{} is valid", path.display());
+
+    Ok(())
+}
+
+fn check(path: &Path, config: &Config) -> Result<(), PagerDutyError> {
+    let problems = config.validate();
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    Err(invalid(
+        path,
+        format!("{} problem(s) found", problems.len()),
+    ))
+}
+
+fn invalid(path: &Path, message: String) -> PagerDutyError {
+    PagerDutyError::Config {
+        path: path.display().to_string(),
+        message,
+    }
+}
+
+/// Enough of the token to tell two apart.
+fn mask(token: &str) -> String {
+    if token.chars().count() <= 8 {
+        return "****".into();
+    }
+    let visible: String = token
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<char>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("****{}", visible)
+}
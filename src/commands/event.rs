@@ -0,0 +1,287 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{Map, Value};
+
+use crate::v2::{
+    AlertEvent, AlertPayload, ChangeEvent, ChangePayload, EventAction, EventImage, EventLink,
+    EventsClient, PagerDutyError, RequestOptions,
+};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    with_event_args(
+        SubCommand::with_name("event")
+            .about("Trigger, acknowledge or resolve an alert through the Events API")
+            .arg(
+                Arg::with_name("action")
+                    .required(true)
+                    .possible_values(&["trigger", "acknowledge", "resolve"])
+                    .help("What to do with the alert."),
+            )
+            .arg(
+                Arg::with_name("dedup_key")
+                    .long("dedup-key")
+                    .takes_value(true)
+                    .required_ifs(&[("action", "acknowledge"), ("action", "resolve")])
+                    .help("Identifies the alert, required to acknowledge or resolve one."),
+            )
+            .arg(
+                Arg::with_name("severity")
+                    .long("severity")
+                    .takes_value(true)
+                    .required_if("action", "trigger")
+                    .possible_values(&["critical", "error", "warning", "info"])
+                    .help("How bad the problem is, required to trigger."),
+            )
+            .arg(
+                Arg::with_name("component")
+                    .long("component")
+                    .takes_value(true)
+                    .help("Part of the source that is responsible, like `mysql`."),
+            )
+            .arg(
+                Arg::with_name("group")
+                    .long("group")
+                    .takes_value(true)
+                    .help("Logical grouping of sources, like `prod-datapipe`."),
+            )
+            .arg(
+                Arg::with_name("class")
+                    .long("class")
+                    .takes_value(true)
+                    .help("The type of problem, like `disk`."),
+            )
+            .arg(
+                Arg::with_name("image")
+                    .long("image")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Image to show on the alert, as `src`, `src|href` or `src|href|alt`."),
+            ),
+    )
+    .arg(
+        Arg::with_name("summary")
+            .long("summary")
+            .takes_value(true)
+            .required_if("action", "trigger")
+            .help("Summary of the problem, required to trigger."),
+    )
+    .arg(
+        Arg::with_name("source")
+            .long("source")
+            .takes_value(true)
+            .required_if("action", "trigger")
+            .help("Where the problem is, like a hostname. Required to trigger."),
+    )
+}
+
+pub fn change_subcommand<'a, 'b>() -> App<'a, 'b> {
+    with_event_args(
+        SubCommand::with_name("change-event")
+            .about("Send a change event, like a deploy, through the Events API"),
+    )
+    .arg(
+        Arg::with_name("summary")
+            .long("summary")
+            .takes_value(true)
+            .required(true)
+            .help("Summary of the change."),
+    )
+    .arg(
+        Arg::with_name("source")
+            .long("source")
+            .takes_value(true)
+            .help("Where the change happened, like a hostname."),
+    )
+}
+
+/// Arguments shared by alert and change events.
+fn with_event_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("routing_key")
+            .short("k")
+            .long("routing-key")
+            .takes_value(true)
+            .required(true)
+            .env("PAGERDUTY_ROUTING_KEY")
+            .help("Integration key of the service the event is for."),
+    )
+    .arg(
+        Arg::with_name("events_url")
+            .long("events-url")
+            .takes_value(true)
+            .env("PAGERDUTY_EVENTS_URL")
+            .default_value("https://events.pagerduty.com")
+            .help("Base URL of the PagerDuty Events API. Useful for proxies, other regions or a local mock server."),
+    )
+    .arg(
+        Arg::with_name("timestamp")
+            .long("timestamp")
+            .takes_value(true)
+            .validator(super::is_timestamp)
+            .help("When it happened. Defaults to when PagerDuty receives the event."),
+    )
+    .arg(
+        Arg::with_name("detail")
+            .long("detail")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(is_detail)
+            .help("Custom detail as `key=value`. Applied after --details-file."),
+    )
+    .arg(
+        Arg::with_name("details_file")
+            .long("details-file")
+            .takes_value(true)
+            .help("JSON file with an object of custom details."),
+    )
+    .arg(
+        Arg::with_name("link")
+            .long("link")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Link to include, as `href` or `href|text`."),
+    )
+}
+
+fn is_detail(arg: String) -> Result<(), String> {
+    if arg.contains('=') {
+        Ok(())
+    } else {
+        Err(format!("`{}` is not a `key=value` pair", arg))
+    }
+}
+
+pub async fn run(options: RequestOptions, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    let client = EventsClient::new(args.value_of("events_url").unwrap(), options);
+
+    let event_action = match args.value_of("action").unwrap() {
+        "trigger" => EventAction::Trigger,
+        "acknowledge" => EventAction::Acknowledge,
+        "resolve" => EventAction::Resolve,
+        _ => unreachable!(),
+    };
+
+    let payload = match event_action {
+        EventAction::Trigger => Some(AlertPayload {
+            summary: args.value_of("summary").unwrap().to_string(),
+            source: args.value_of("source").unwrap().to_string(),
+            severity: args.value_of("severity").unwrap().to_string(),
+            timestamp: args.value_of("timestamp").map(String::from),
+            component: args.value_of("component").map(String::from),
+            group: args.value_of("group").map(String::from),
+            class: args.value_of("class").map(String::from),
+            custom_details: custom_details(args),
+        }),
+        _ => None,
+    };
+
+    let event = AlertEvent {
+        routing_key: args.value_of("routing_key").unwrap().to_string(),
+        event_action,
+        dedup_key: args.value_of("dedup_key").map(String::from),
+        payload,
+        links: links(args),
+        images: args
+            .values_of("image")
+            .unwrap_or_default()
+            .map(|image| {
+                let mut parts = image.splitn(3, '|').map(String::from);
+                EventImage {
+                    src: parts.next().unwrap_or_default(),
+                    href: parts.next(),
+                    alt: parts.next(),
+                }
+            })
+            .collect(),
+    };
+
+    let resp = client.send_event(&event).await?;
+    match resp.dedup_key {
+        Some(dedup_key) => println!(
+            "{} - {} (dedup key {})",
+            resp.status, resp.message, dedup_key
+        ),
+        None => println!("{} - {}", resp.status, resp.message),
+    }
+
+    Ok(())
+}
+
+pub async fn run_change(
+    options: RequestOptions,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let client = EventsClient::new(args.value_of("events_url").unwrap(), options);
+
+    let event = ChangeEvent {
+        routing_key: args.value_of("routing_key").unwrap().to_string(),
+        payload: ChangePayload {
+            summary: args.value_of("summary").unwrap().to_string(),
+            source: args.value_of("source").map(String::from),
+            timestamp: args.value_of("timestamp").map(String::from),
+            custom_details: custom_details(args),
+        },
+        links: links(args),
+    };
+
+    let resp = client.send_change_event(&event).await?;
+    println!("{} - {}", resp.status, resp.message);
+
+    Ok(())
+}
+
+fn links(args: &ArgMatches<'_>) -> Vec<EventLink> {
+    args.values_of("link")
+        .unwrap_or_default()
+        .map(|link| {
+            let mut parts = link.splitn(2, '|').map(String::from);
+            EventLink {
+                href: parts.next().unwrap_or_default(),
+                text: parts.next(),
+            }
+        })
+        .collect()
+}
+
+/// Merge `--details-file` and `--detail` into one object, `None` when neither was given.
+fn custom_details(args: &ArgMatches<'_>) -> Option<Value> {
+    let mut details = match args.value_of("details_file") {
+        Some(path) => read_details_file(path),
+        None => Map::new(),
+    };
+
+    for detail in args.values_of("detail").unwrap_or_default() {
+        let mut parts = detail.splitn(2, '=');
+        let key = parts.next().unwrap_or_default();
+        let value = parts.next().unwrap_or_default();
+        details.insert(key.to_string(), Value::String(value.to_string()));
+    }
+
+    if details.is_empty() && !args.is_present("details_file") {
+        None
+    } else {
+        Some(Value::Object(details))
+    }
+}
+
+fn read_details_file(path: &str) -> Map<String, Value> {
+    let details = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read `{}`: {}", path, e))
+        .and_then(|contents| {
+            serde_json::from_str::<Value>(&contents)
+                .map_err(|e| format!("`{}` is not valid JSON: {}", path, e))
+        })
+        .and_then(|value| match value {
+            Value::Object(details) => Ok(details),
+            _ => Err(format!("`{}` must contain a JSON object", path)),
+        });
+
+    match details {
+        Ok(details) => details,
+        Err(message) => {
+            clap::Error::with_description(&message, clap::ErrorKind::InvalidValue).exit()
+        }
+    }
+}
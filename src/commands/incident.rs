@@ -0,0 +1,207 @@
+use clap::{clap_app, App, Arg, ArgMatches};
+
+use crate::filter;
+use crate::v2::{IncidentAction, IncidentQuery, PagerDutyClient, PagerDutyError};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(incident =>
+        (about: "Acknowledge, resolve, reassign, snooze or merge incidents")
+        (@setting SubcommandRequiredElseHelp)
+    )
+    .subcommand(with_targets(clap_app!(ack =>
+        (about: "Acknowledge incidents")
+    )))
+    .subcommand(with_targets(clap_app!(resolve =>
+        (about: "Resolve incidents")
+    )))
+    .subcommand(with_targets(clap_app!(reassign =>
+        (about: "Reassign incidents to a user or escalation policy")
+        (@group assignee +required =>
+            (@arg to_user: --("to-user") +takes_value "ID or email of the user to assign the incidents to, or `me`.")
+            (@arg to_policy: --("to-policy") +takes_value "ID of the escalation policy to assign the incidents to.")
+        )
+    )))
+    .subcommand(with_targets(clap_app!(snooze =>
+        (about: "Snooze incidents")
+        (@arg duration: --for +takes_value +required {super::is_duration} "How long to snooze for, like 30m or 2h.")
+    )))
+    .subcommand(with_targets(clap_app!(merge =>
+        (about: "Merge incidents into another incident")
+        (@arg into: --into +takes_value +required "ID of the incident that the others will be merged into.")
+    )))
+}
+
+/// Every action can target a list of IDs, or every incident matching a filter.
+fn with_targets<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("from")
+            .long("from")
+            .takes_value(true)
+            .required(true)
+            .env("PAGERDUTY_FROM")
+            .help("Email of the PagerDuty user the changes are made on behalf of."),
+    )
+    .arg(
+        Arg::with_name("incident_id")
+            .multiple(true)
+            .help("IDs of the incidents to update. When omitted the filters are used."),
+    )
+    .arg(
+        Arg::with_name("status")
+            .long("status")
+            .takes_value(true)
+            .multiple(true)
+            .possible_values(&["triggered", "acknowledged", "resolved"])
+            .help("Update incidents with this status. Defaults to triggered and acknowledged."),
+    )
+    .arg(
+        Arg::with_name("urgency")
+            .long("urgency")
+            .takes_value(true)
+            .multiple(true)
+            .possible_values(&["high", "low"])
+            .help("Update incidents with this urgency."),
+    )
+    .arg(
+        Arg::with_name("service")
+            .short("s")
+            .long("service")
+            .takes_value(true)
+            .multiple(true)
+            .help("Regex that when matches the service name will include the incident."),
+    )
+    .arg(
+        Arg::with_name("exclude_service")
+            .short("x")
+            .long("exclude-service")
+            .takes_value(true)
+            .multiple(true)
+            .help("Regex that when matches the service name will exclude the incident."),
+    )
+    .arg(
+        Arg::with_name("since")
+            .long("since")
+            .takes_value(true)
+            .validator(super::is_timestamp)
+            .help("Update incidents created after this time."),
+    )
+    .arg(
+        Arg::with_name("until")
+            .long("until")
+            .takes_value(true)
+            .validator(super::is_timestamp)
+            .help("Update incidents created before this time."),
+    )
+}
+
+pub async fn run(client: PagerDutyClient, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    let (name, args) = match args.subcommand() {
+        (name, Some(args)) => (name, args),
+        _ => unreachable!(),
+    };
+
+    client.check_allowed("update incidents", None).await?;
+
+    let action = match name {
+        "ack" => IncidentAction::Acknowledge,
+        "resolve" => IncidentAction::Resolve,
+        "reassign" => match (args.value_of("to_user"), args.value_of("to_policy")) {
+            (Some(user), _) => IncidentAction::ReassignToUser(client.find_user(user).await?.id),
+            (None, Some(policy)) => IncidentAction::ReassignToPolicy(policy.to_string()),
+            (None, None) => unreachable!(),
+        },
+        "snooze" => {
+            let duration = crate::duration::parse_duration(args.value_of("duration").unwrap())
+                .expect("duration to be validated by clap");
+            IncidentAction::Snooze(duration.num_seconds().max(0) as u64)
+        }
+        "merge" => IncidentAction::MergeInto(args.value_of("into").unwrap().to_string()),
+        _ => unreachable!(),
+    };
+
+    let mut incident_ids = find_incident_ids(&client, args).await?;
+    if let IncidentAction::MergeInto(target) = &action {
+        incident_ids.retain(|id| id != target);
+    }
+
+    if incident_ids.is_empty() {
+        println!("No incidents matched.");
+        return Ok(());
+    }
+
+    let results = client
+        .update_incidents(args.value_of("from").unwrap(), &incident_ids, &action)
+        .await;
+
+    println!(
+        "{}",
+        crate::output::incident::build_action_summary(&results)
+    );
+
+    match results.into_iter().find_map(|result| result.result.err()) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+async fn find_incident_ids(
+    client: &PagerDutyClient,
+    args: &ArgMatches<'_>,
+) -> Result<Vec<String>, PagerDutyError> {
+    if let Some(ids) = args.values_of("incident_id") {
+        return Ok(ids.map(String::from).collect());
+    }
+
+    let has_filter = [
+        "status",
+        "urgency",
+        "service",
+        "exclude_service",
+        "since",
+        "until",
+        "team",
+    ]
+    .iter()
+    .any(|name| args.is_present(name));
+    if !has_filter {
+        clap::Error::with_description(
+            "Provide the incident IDs to update, or at least one filter to select them",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit();
+    }
+
+    let include_vec = filter::regex_values(args, "service");
+    let exclude_vec = filter::regex_values(args, "exclude_service");
+
+    let mut statuses: Vec<String> = args
+        .values_of("status")
+        .unwrap_or_default()
+        .map(String::from)
+        .collect();
+    if statuses.is_empty() {
+        statuses = vec!["triggered".to_string(), "acknowledged".to_string()];
+    }
+
+    let query = IncidentQuery {
+        statuses,
+        urgencies: args
+            .values_of("urgency")
+            .unwrap_or_default()
+            .map(String::from)
+            .collect(),
+        since: args.value_of("since").map(String::from),
+        until: args.value_of("until").map(String::from),
+        team_ids: filter::team_ids(client, args).await?,
+    };
+
+    Ok(client
+        .fetch_incidents(&query)
+        .await?
+        .into_iter()
+        .filter(|incident| {
+            filter::should_be_included(&include_vec, &exclude_vec, &incident.service_name)
+        })
+        .map(|incident| incident.id)
+        .collect())
+}
@@ -0,0 +1,116 @@
+use clap::{clap_app, App, ArgMatches};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, Config};
+use crate::v2::{CacheMode, CacheOptions, PagerDutyClient, PagerDutyError, RequestOptions};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(login =>
+        (about: "Check an API token works and save it, so it isn't needed on the command line")
+        (@arg token_command: --("token-command") +takes_value "Command that prints the token, like `op read op://Private/PagerDuty/token`. The command is saved instead of the token and run whenever one is needed.")
+    )
+}
+
+pub fn logout_subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(logout =>
+        (about: "Forget the token saved by login for the profile")
+    )
+}
+
+pub async fn run(
+    config_path: Option<PathBuf>,
+    api_url: &str,
+    options: RequestOptions,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let path = super::config::required_path(config_path)?;
+    let name = profile_name(&path, args)?;
+
+    let token_command = args.value_of("token_command");
+    let token = match (token_command, args.value_of("API_TOKEN")) {
+        (Some(command), _) => config::run_token_command(command)?,
+        (None, Some(token)) => token.to_string(),
+        (None, None) => read_token(),
+    };
+
+    // Always ask PagerDuty, a cached `/users/me` says nothing about this token.
+    let options = RequestOptions {
+        cache: CacheOptions {
+            mode: CacheMode::Disabled,
+            dir: None,
+        },
+        ..options
+    };
+    let user = PagerDutyClient::new(&token, api_url, options)
+        .validate_token()
+        .await?;
+
+    let credentials_path = config::credentials_path(&path);
+    let mut credentials = Config::load(&credentials_path)?;
+    let saved = credentials.profiles.entry(name.clone()).or_default();
+    match token_command {
+        Some(command) => {
+            saved.token = None;
+            saved.token_command = Some(command.to_string());
+        }
+        None => {
+            saved.token = Some(token);
+            saved.token_command = None;
+        }
+    }
+    credentials.save(&credentials_path)?;
+
+    match user {
+        Some(user) => println!("Logged in to profile {} as {}", name, user.to_display()),
+        None => println!("Logged in to profile {} with an account token", name),
+    }
+
+    Ok(())
+}
+
+pub fn run_logout(
+    config_path: Option<PathBuf>,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let path = super::config::required_path(config_path)?;
+    let name = profile_name(&path, args)?;
+
+    let credentials_path = config::credentials_path(&path);
+    let mut credentials = Config::load(&credentials_path)?;
+    if credentials.profiles.remove(&name).is_none() {
+        println!("Not logged in to profile {}", name);
+        return Ok(());
+    }
+
+    credentials.save(&credentials_path)?;
+    println!("Logged out of profile {}", name);
+
+    Ok(())
+}
+
+fn profile_name(path: &Path, args: &ArgMatches<'_>) -> Result<String, PagerDutyError> {
+    let config = Config::load(path)?;
+    Ok(config.profile_name(args.value_of("profile")).to_string())
+}
+
+/// Ask for the token without echoing it, or read the first line when stdin isn't a terminal.
+fn read_token() -> String {
+    let token = if atty::is(atty::Stream::Stdin) {
+        rpassword::read_password_from_tty(Some("PagerDuty API token: ")).unwrap_or_default()
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line).ok();
+        line
+    };
+
+    let token = token.trim();
+    if token.is_empty() {
+        clap::Error::with_description(
+            "No API token given, use --api-token, --token-command or type it when asked",
+            clap::ErrorKind::EmptyValue,
+        )
+        .exit()
+    }
+    token.to_string()
+}
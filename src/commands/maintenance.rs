@@ -0,0 +1,143 @@
+use chrono::Utc;
+use clap::{clap_app, App, Arg, ArgMatches};
+
+use crate::config::Profile;
+use crate::filter;
+use crate::output;
+use crate::v2::{MaintenanceFilter, PagerDutyClient, PagerDutyError};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(maintenance =>
+        (about: "Start, list and end maintenance windows")
+        (@setting SubcommandRequiredElseHelp)
+        (@subcommand list =>
+            (about: "List maintenance windows")
+            (@arg ongoing: --ongoing conflicts_with[future] "Only list windows that are in progress.")
+            (@arg future: --future "Only list windows that haven't started yet.")
+            (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json csv] "Format the maintenance windows should be displayed in.")
+        )
+        (@subcommand end =>
+            (about: "End maintenance windows early, or delete ones that haven't started")
+            (@arg window_id: +required +multiple "IDs of the maintenance windows to end.")
+        )
+    )
+    .subcommand(
+        clap_app!(start =>
+            (about: "Put services into maintenance, starting now")
+            (@arg service: -s --service +takes_value +multiple +required "Regex that when matches the service name will put it into maintenance. Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+            (@arg exclude_service: -x --("exclude-service") +takes_value +multiple "Regex that when matches the service name will leave it out. Service takes precedence.")
+            (@arg duration: -d --duration +takes_value +required {super::is_duration} "How long the maintenance lasts, like 30m or 2h.")
+            (@arg description: --description +takes_value "Why the services are in maintenance.")
+        )
+        .arg(
+            Arg::with_name("from")
+                .long("from")
+                .takes_value(true)
+                .required(true)
+                .env("PAGERDUTY_FROM")
+                .help("Email of the PagerDuty user the window is created on behalf of."),
+        ),
+    )
+}
+
+pub async fn run(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    match args.subcommand() {
+        ("start", Some(args)) => start(client, args).await,
+        ("list", Some(args)) => list(client, profile, args).await,
+        ("end", Some(args)) => end(client, args).await,
+        _ => unreachable!(),
+    }
+}
+
+async fn start(client: PagerDutyClient, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    let duration = crate::duration::parse_duration(args.value_of("duration").unwrap())
+        .expect("duration to be validated by clap");
+    let now = Utc::now();
+    let end = now
+        .checked_add_signed(duration)
+        .expect("parse_duration to cap durations far below chrono's range");
+
+    client.check_allowed("start maintenance", None).await?;
+
+    let include_vec = filter::regex_values(args, "service");
+    let exclude_vec = filter::regex_values(args, "exclude_service");
+
+    let services = client
+        .fetch_services(|name| filter::should_be_included(&include_vec, &exclude_vec, name))
+        .await?;
+
+    if services.is_empty() {
+        println!("No services matched.");
+        return Ok(());
+    }
+
+    let window = client
+        .create_maintenance_window(
+            args.value_of("from").unwrap(),
+            &services,
+            &now.to_rfc3339(),
+            &end.to_rfc3339(),
+            args.value_of("description"),
+        )
+        .await?;
+
+    println!("{}", output::maintenance::build_tree_output(vec![window]));
+
+    Ok(())
+}
+
+async fn list(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let filter = if args.is_present("ongoing") {
+        MaintenanceFilter::Ongoing
+    } else if args.is_present("future") {
+        MaintenanceFilter::Future
+    } else {
+        MaintenanceFilter::All
+    };
+
+    let team_ids = filter::team_ids(&client, args).await?;
+
+    let mut windows = client.fetch_maintenance_windows(filter).await?;
+    windows.retain(|window| filter::is_owned_by(&team_ids, &window.teams));
+
+    let output = match profile.format(args, &["tree", "json", "csv"]) {
+        "tree" => output::maintenance::build_tree_output(windows),
+        "json" => output::maintenance::build_json_output(windows),
+        "csv" => output::maintenance::build_csv_output(windows),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
+
+async fn end(client: PagerDutyClient, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    client
+        .check_allowed("end maintenance windows", None)
+        .await?;
+
+    let mut first_error = None;
+    for window_id in args.values_of("window_id").unwrap() {
+        match client.end_maintenance_window(window_id).await {
+            Ok(()) => println!("{} - ended", window_id),
+            Err(e) => {
+                println!("{} - failed: {}", window_id, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
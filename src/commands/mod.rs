@@ -0,0 +1,25 @@
+pub mod config;
+pub mod event;
+pub mod incident;
+pub mod login;
+pub mod maintenance;
+pub mod overrides;
+pub mod page_who;
+pub mod services;
+pub mod teams;
+pub mod users;
+pub mod whoami;
+
+pub fn is_timestamp(arg: String) -> Result<(), String> {
+    match chrono::DateTime::parse_from_rfc3339(&arg) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!(
+            "`{}` is not an ISO 8601 timestamp (2020-04-01T12:00:00Z)",
+            arg
+        )),
+    }
+}
+
+pub fn is_duration(arg: String) -> Result<(), String> {
+    crate::duration::parse_duration(&arg).map(|_| ())
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Duration, Utc};
+use clap::{clap_app, App, ArgMatches};
+
+use crate::config::Profile;
+use crate::filter;
+use crate::output;
+use crate::v2::{cacheable_now, PagerDutyClient, PagerDutyError};
+
+/// Accounts without it can't change schedules, overrides included.
+const MANAGE_SCHEDULES: &str = "manage_schedules";
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(overrides =>
+        (name: "override")
+        (about: "Create, list and delete schedule overrides")
+        (@setting SubcommandRequiredElseHelp)
+        (@subcommand create =>
+            (about: "Put someone on call in place of the schedule's rotation")
+            (@arg schedule: -s --schedule +takes_value +required "Name or ID of the schedule to override.")
+            (@arg user: -u --user +takes_value +required "ID or email of the user to put on call, or `me`.")
+            (@arg start: --start +takes_value +required {super::is_timestamp} "When the override starts.")
+            (@arg end: --end +takes_value +required {super::is_timestamp} "When the override ends.")
+            (@arg dry_run: --("dry-run") "Show the final schedule with the override applied, without creating it.")
+        )
+        (@subcommand list =>
+            (about: "List the overrides on a schedule")
+            (@arg schedule: -s --schedule +takes_value +required "Name or ID of the schedule.")
+            (@arg since: --since +takes_value {super::is_timestamp} "Start of the window to list. Defaults to now.")
+            (@arg until: --until +takes_value {super::is_timestamp} "End of the window to list. Defaults to a week after since.")
+            (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json csv] "Format the overrides should be displayed in.")
+        )
+        (@subcommand delete =>
+            (about: "Delete overrides from a schedule")
+            (@arg schedule: -s --schedule +takes_value +required "Name or ID of the schedule.")
+            (@arg override_id: +required +multiple "IDs of the overrides to delete.")
+        )
+    )
+}
+
+pub async fn run(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    match args.subcommand() {
+        ("create", Some(args)) => create(client, args).await,
+        ("list", Some(args)) => list(client, profile, args).await,
+        ("delete", Some(args)) => delete(client, args).await,
+        _ => unreachable!(),
+    }
+}
+
+fn timestamp(args: &ArgMatches<'_>, name: &str) -> Option<DateTime<Utc>> {
+    args.value_of(name).map(|value| {
+        DateTime::parse_from_rfc3339(value)
+            .expect("timestamp to be validated by clap")
+            .with_timezone(&Utc)
+    })
+}
+
+async fn create(client: PagerDutyClient, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    let start = timestamp(args, "start").unwrap();
+    let end = timestamp(args, "end").unwrap();
+    if end <= start {
+        clap::Error::with_description("--end must be after --start", clap::ErrorKind::InvalidValue)
+            .exit();
+    }
+
+    // Render a day either side so the preview shows who hands over to and from the override.
+    let since = (start - Duration::days(1)).to_rfc3339();
+    let until = (end + Duration::days(1)).to_rfc3339();
+    let (schedule, user) = futures::join!(
+        client.find_schedule(args.value_of("schedule").unwrap(), &since, &until),
+        client.find_user(args.value_of("user").unwrap())
+    );
+    let (schedule, user) = (schedule?, user?);
+
+    let (start, end) = (start.to_rfc3339(), end.to_rfc3339());
+
+    if args.is_present("dry_run") {
+        let preview = schedule.preview_override(&start, &end, &user);
+        println!(
+            "{}",
+            output::schedule::build_preview_output(&schedule, &preview)
+        );
+        return Ok(());
+    }
+
+    client
+        .check_allowed("create overrides", Some(MANAGE_SCHEDULES))
+        .await?;
+    let created = client
+        .create_override(&schedule.id, &start, &end, &user)
+        .await?;
+
+    println!(
+        "{}",
+        output::schedule::build_overrides_tree_output(&schedule, vec![created])
+    );
+
+    Ok(())
+}
+
+async fn list(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let since = timestamp(args, "since");
+    let until = timestamp(args, "until");
+    // The schedule is only rendered for its name, so a cacheable window is close enough.
+    let schedule_since = since.unwrap_or_else(|| cacheable_now("schedules"));
+    let schedule_until = until.unwrap_or_else(|| schedule_since + Duration::weeks(1));
+    let schedule = client
+        .find_schedule(
+            args.value_of("schedule").unwrap(),
+            &schedule_since.to_rfc3339(),
+            &schedule_until.to_rfc3339(),
+        )
+        .await?;
+
+    let since = since.unwrap_or_else(Utc::now);
+    let until = until.unwrap_or_else(|| since + Duration::weeks(1));
+    let (since, until) = (since.to_rfc3339(), until.to_rfc3339());
+    let team_ids = filter::team_ids(&client, args).await?;
+    let overrides = if filter::is_owned_by(&team_ids, &schedule.teams) {
+        client.fetch_overrides(&schedule.id, &since, &until).await?
+    } else {
+        Vec::new()
+    };
+
+    let output = match profile.format(args, &["tree", "json", "csv"]) {
+        "tree" => output::schedule::build_overrides_tree_output(&schedule, overrides),
+        "json" => output::schedule::build_overrides_json_output(&schedule, overrides),
+        "csv" => output::schedule::build_overrides_csv_output(&schedule, overrides),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
+
+async fn delete(client: PagerDutyClient, args: &ArgMatches<'_>) -> Result<(), PagerDutyError> {
+    client
+        .check_allowed("delete overrides", Some(MANAGE_SCHEDULES))
+        .await?;
+
+    let now = cacheable_now("schedules").to_rfc3339();
+    let schedule = client
+        .find_schedule(args.value_of("schedule").unwrap(), &now, &now)
+        .await?;
+
+    let mut first_error = None;
+    for override_id in args.values_of("override_id").unwrap() {
+        match client.delete_override(&schedule.id, override_id).await {
+            Ok(()) => println!("{} - deleted", override_id),
+            Err(e) => {
+                println!("{} - failed: {}", override_id, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
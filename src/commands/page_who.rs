@@ -0,0 +1,101 @@
+use clap::{clap_app, App, Arg, ArgMatches};
+use std::collections::BTreeSet;
+
+use crate::config::Profile;
+use crate::filter;
+use crate::output;
+use crate::v2::{PagerDutyClient, PagerDutyError};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(page_who =>
+        (name: "page-who")
+        (about: "Show who to page for a service, level by level with how to reach them")
+        (@arg service: +required "ID or name of the service, or a regex matching its name. Names are matched ignoring case.")
+        (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json] "Format the on call chain should be displayed in.")
+        (@arg open_incident: --("open-incident") +takes_value "Open an incident on the service with this title.")
+        (@arg urgency: --urgency +takes_value default_value("high") possible_value[high low] "Urgency of the incident that is opened.")
+    )
+    .arg(
+        Arg::with_name("from")
+            .long("from")
+            .takes_value(true)
+            .env("PAGERDUTY_FROM")
+            .help("Email of the PagerDuty user the incident is opened on behalf of. Defaults to the owner of the token."),
+    )
+}
+
+pub async fn run(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let query = args.value_of("service").unwrap();
+
+    let (policies, services) = client.fetch_policies_and_services().await?;
+    let services = filter::services_named(services, query);
+    if services.is_empty() {
+        return Err(PagerDutyError::NotFound {
+            kind: "service",
+            name: query.to_string(),
+        });
+    }
+    if args.is_present("open_incident") && services.len() > 1 {
+        return Err(PagerDutyError::Ambiguous {
+            kind: "service",
+            name: query.to_string(),
+            matches: services.into_iter().map(|service| service.name).collect(),
+        });
+    }
+
+    let mut chains = Vec::new();
+    for service in services {
+        let policy = policies
+            .iter()
+            .find(|policy| policy.id == service.escalation_policy_id)
+            .cloned()
+            .ok_or_else(|| PagerDutyError::NotFound {
+                kind: "escalation policy",
+                name: service.escalation_policy_name.clone(),
+            })?;
+        chains.push((service, policy));
+    }
+
+    let user_ids: BTreeSet<String> = chains
+        .iter()
+        .flat_map(|(_, policy)| policy.oncall_groups.iter())
+        .flat_map(|group| group.users.iter())
+        .map(|user| user.id.clone())
+        .collect();
+    let contact_methods = client
+        .fetch_contact_methods(&user_ids.into_iter().collect::<Vec<String>>())
+        .await?;
+
+    let output = match profile.format(args, &["tree", "json"]) {
+        "tree" => output::page_who::build_tree_output(&chains, &contact_methods),
+        "json" => output::page_who::build_json_output(&chains, &contact_methods),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    let (service, title) = match (chains.first(), args.value_of("open_incident")) {
+        (Some((service, _)), Some(title)) => (service, title),
+        _ => return Ok(()),
+    };
+
+    client.check_allowed("open incidents", None).await?;
+    let from = match args.value_of("from") {
+        Some(from) => from.to_string(),
+        None => client.find_user("me").await?.email,
+    };
+
+    let incident = client
+        .create_incident(&from, service, title, args.value_of("urgency").unwrap())
+        .await?;
+    println!(
+        "Opened incident #{} on {} - {}",
+        incident.incident_number, service.name, incident.html_url
+    );
+
+    Ok(())
+}
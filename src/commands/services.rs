@@ -0,0 +1,41 @@
+use clap::{clap_app, App, ArgMatches};
+
+use crate::config::Profile;
+use crate::filter;
+use crate::output;
+use crate::v2::{PagerDutyClient, PagerDutyError};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(services =>
+        (about: "List services with their status, urgency, timeouts, alert grouping, integrations and escalation policy")
+        (@arg include: -i --include +takes_value +multiple "Regex that when matches will include the service. Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+        (@arg exclude: -x --exclude +takes_value +multiple "Regex that when matches will exclude the service. Include takes precedence.")
+        (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json csv] "Format the services should be displayed in.")
+    )
+}
+
+pub async fn run(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let include_vec = filter::regex_values(args, "include");
+    let exclude_vec = filter::regex_values(args, "exclude");
+    let team_ids = filter::team_ids(&client, args).await?;
+
+    let mut services = client
+        .fetch_services(|name| filter::should_be_included(&include_vec, &exclude_vec, name))
+        .await?;
+    services.retain(|service| filter::is_owned_by(&team_ids, &service.teams));
+
+    let output = match profile.format(args, &["tree", "json", "csv"]) {
+        "tree" => output::service::build_tree_output(services),
+        "json" => output::service::build_json_output(services),
+        "csv" => output::service::build_csv_output(services),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
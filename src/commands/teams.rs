@@ -0,0 +1,43 @@
+use clap::{clap_app, App, ArgMatches};
+
+use crate::config::Profile;
+use crate::filter;
+use crate::output;
+use crate::v2::{PagerDutyClient, PagerDutyError};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(teams =>
+        (about: "List teams with their members and the escalation policies and services they own")
+        (@arg include: -i --include +takes_value +multiple "Regex that when matches will include the team. Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+        (@arg exclude: -x --exclude +takes_value +multiple "Regex that when matches will exclude the team. Include takes precedence.")
+        (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json csv] "Format the teams should be displayed in.")
+    )
+}
+
+pub async fn run(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let include_vec = filter::regex_values(args, "include");
+    let exclude_vec = filter::regex_values(args, "exclude");
+    let team_ids = filter::team_ids(&client, args).await?;
+
+    let teams = client
+        .fetch_team_details(|team| {
+            filter::should_be_included(&include_vec, &exclude_vec, &team.name)
+                && (team_ids.is_empty() || team_ids.contains(&team.id))
+        })
+        .await?;
+
+    let output = match profile.format(args, &["tree", "json", "csv"]) {
+        "tree" => output::team::build_tree_output(teams),
+        "json" => output::team::build_json_output(teams),
+        "csv" => output::team::build_csv_output(teams),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
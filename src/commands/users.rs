@@ -0,0 +1,46 @@
+use clap::{clap_app, App, ArgMatches};
+
+use crate::config::Profile;
+use crate::filter;
+use crate::output;
+use crate::v2::{PagerDutyClient, PagerDutyError};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(users =>
+        (about: "List users with their contact methods and notification rules, flagging anyone who might not be reached")
+        (@arg include: -i --include +takes_value +multiple "Regex that when matches will include the user. Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+        (@arg exclude: -x --exclude +takes_value +multiple "Regex that when matches will exclude the user. Include takes precedence.")
+        (@arg not_ready: --("not-ready") "Only list users with no phone contact method or no high-urgency notification rule.")
+        (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json csv] "Format the users should be displayed in.")
+    )
+}
+
+pub async fn run(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let include_vec = filter::regex_values(args, "include");
+    let exclude_vec = filter::regex_values(args, "exclude");
+    let team_ids = filter::team_ids(&client, args).await?;
+    let not_ready = args.is_present("not_ready");
+
+    let users = client
+        .fetch_users(|user| {
+            filter::should_be_included(&include_vec, &exclude_vec, &user.name)
+                && filter::is_owned_by(&team_ids, &user.teams)
+                && (!not_ready || !user.readiness_problems().is_empty())
+        })
+        .await?;
+
+    let output = match profile.format(args, &["tree", "json", "csv"]) {
+        "tree" => output::user::build_tree_output(users),
+        "json" => output::user::build_json_output(users),
+        "csv" => output::user::build_csv_output(users),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use clap::{clap_app, App, ArgMatches};
+
+use crate::config::Profile;
+use crate::output;
+use crate::v2::{PagerDutyClient, PagerDutyError};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(whoami =>
+        (about: "Show who the API token belongs to, the account it's for and what the account can do")
+        (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json] "Format the token details should be displayed in.")
+    )
+}
+
+pub fn abilities_subcommand<'a, 'b>() -> App<'a, 'b> {
+    clap_app!(abilities =>
+        (about: "List the account's abilities, or check it has the ones given")
+        (@arg ability: +multiple "Abilities to check for, like `teams`. Exits with 13 when one is missing.")
+    )
+}
+
+pub async fn run(
+    client: PagerDutyClient,
+    profile: &Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let info = client.fetch_token_info().await?;
+
+    let output = match profile.format(args, &["tree", "json"]) {
+        "tree" => output::whoami::build_tree_output(info),
+        "json" => output::whoami::build_json_output(info),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
+
+pub async fn run_abilities(
+    client: PagerDutyClient,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let info = client.fetch_token_info().await?;
+
+    let wanted: Vec<&str> = match args.values_of("ability") {
+        Some(abilities) => abilities.collect(),
+        None => {
+            for ability in &info.abilities {
+                println!("{}", ability);
+            }
+            return Ok(());
+        }
+    };
+
+    let mut missing = Vec::new();
+    for ability in wanted {
+        if info.abilities.iter().any(|a| a == ability) {
+            println!("{} - yes", ability);
+        } else {
+            println!("{} - no", ability);
+            missing.push(format!("`{}`", ability));
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(PagerDutyError::NotAllowed {
+        action: "use every ability asked for",
+        reason: format!("the account doesn't have {}", missing.join(", ")),
+    })
+}
@@ -0,0 +1,480 @@
+use clap::ArgMatches;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::v2::PagerDutyError;
+
+/// Formats a profile's `format` can name. Commands that can't write it use their own default.
+pub const FORMATS: [&str; 3] = ["tree", "json", "csv"];
+
+/// The profile used when neither `--profile` nor `default_profile` names one.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings for one PagerDuty account. Anything left out falls back to clap's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Prints the token, so it can stay in a password manager.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(
+        deserialize_with = "string_or_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub include: Vec<String>,
+    #[serde(
+        deserialize_with = "string_or_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub exclude: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// `$XDG_CONFIG_HOME/pagerduty-cli/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("pagerduty-cli").join("config.toml"))
+}
+
+/// Where `login` saves tokens, next to the config file so `--config` moves both.
+pub fn credentials_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("credentials.toml")
+}
+
+/// The profile from the config file, with the token `login` saved for it taking the place
+/// of the one in the config file. A profile that was only logged in to is fine, one that
+/// `config validate` would complain about isn't.
+pub fn load_profile(config_path: &Path, name: Option<&str>) -> Result<Profile, PagerDutyError> {
+    let config = Config::load(config_path)?;
+    let credentials = Config::load(&credentials_path(config_path))?;
+    let name = config.profile_name(name);
+    let saved = credentials.profiles.get(name);
+
+    let mut profile = match config.profile(Some(name)) {
+        Err(PagerDutyError::NotFound { .. }) if saved.is_some() => Profile::default(),
+        result => result?,
+    };
+    if let Some(saved) = saved {
+        profile.token = saved.token.clone();
+        profile.token_command = saved.token_command.clone();
+    }
+
+    let problems = profile.validate(name);
+    if !problems.is_empty() {
+        return Err(config_error(config_path, problems.join("; ")));
+    }
+
+    Ok(profile)
+}
+
+impl Config {
+    /// A missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Self, PagerDutyError> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|e| config_error(path, e.to_string()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(config_error(path, e.to_string())),
+        }
+    }
+
+    /// Rewrites the whole file, so comments are lost. Only the owner can read it as it holds tokens.
+    pub fn save(&self, path: &Path) -> Result<(), PagerDutyError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| config_error(path, e.to_string()))?;
+        }
+        let contents = toml::to_string(self).map_err(|e| config_error(path, e.to_string()))?;
+        write_private(path, &contents).map_err(|e| config_error(path, e.to_string()))
+    }
+
+    /// The profile named on the command line, then `default_profile`, then `default`.
+    pub fn profile_name<'a>(&'a self, name: Option<&'a str>) -> &'a str {
+        name.or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// The profile named on the command line, or the default one. No profile at all is fine,
+    /// but naming one that doesn't exist isn't.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile, PagerDutyError> {
+        let name = self.profile_name(name);
+        match self.profiles.get(name) {
+            Some(profile) => Ok(profile.clone()),
+            None if name == DEFAULT_PROFILE => Ok(Profile::default()),
+            None => Err(PagerDutyError::NotFound {
+                kind: "profile",
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Everything that would make a command fail, rather than just what breaks parsing.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(name) = &self.default_profile {
+            if !self.profiles.contains_key(name) {
+                problems.push(format!("default_profile `{}` doesn't exist", name));
+            }
+        }
+
+        for (name, profile) in &self.profiles {
+            problems.extend(profile.validate(name));
+        }
+
+        problems
+    }
+}
+
+impl Profile {
+    /// The problems `Config::validate` reports for this profile.
+    pub fn validate(&self, name: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(api_url) = &self.api_url {
+            if !api_url.starts_with("https://") && !api_url.starts_with("http://") {
+                problems.push(format!(
+                    "[profiles.{}] api_url `{}` isn't a URL",
+                    name, api_url
+                ));
+            }
+        }
+        if let Some(format) = &self.format {
+            if !FORMATS.contains(&format.as_str()) {
+                problems.push(format!(
+                    "[profiles.{}] format `{}` isn't one of {}",
+                    name,
+                    format,
+                    FORMATS.join(", ")
+                ));
+            }
+        }
+        for regex in self.include.iter().chain(&self.exclude) {
+            if let Err(e) = Regex::new(regex) {
+                problems.push(format!(
+                    "[profiles.{}] `{}` isn't a regex: {}",
+                    name, regex, e
+                ));
+            }
+        }
+
+        problems
+    }
+
+    /// Set one of the profile's settings, as named in the config file.
+    pub fn set(&mut self, key: &str, value: Value) -> Result<(), String> {
+        match key {
+            "token" => self.token = Some(value.string()?),
+            "token_command" => self.token_command = Some(value.string()?),
+            "api_url" => self.api_url = Some(value.string()?),
+            "format" => self.format = Some(value.string()?),
+            "include" => self.include = value.strings()?,
+            "exclude" => self.exclude = value.strings()?,
+            "depth" => match value {
+                Value::Integer(depth) if (0..=255).contains(&depth) => {
+                    self.depth = Some(depth as u8)
+                }
+                _ => return Err("depth must be a number (0-255)".to_string()),
+            },
+            _ => return Err(format!("unknown setting `{}`", key)),
+        }
+        Ok(())
+    }
+
+    /// The token, running `token_command` to get it when there isn't one.
+    pub fn resolve_token(&self) -> Result<Option<String>, PagerDutyError> {
+        match (&self.token, &self.token_command) {
+            (Some(token), _) => Ok(Some(token.clone())),
+            (None, Some(command)) => run_token_command(command).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// The format given on the command line, then the profile's if it's one of `formats`, then
+    /// clap's default.
+    pub fn format<'a>(&'a self, args: &'a ArgMatches<'_>, formats: &[&str]) -> &'a str {
+        match &self.format {
+            Some(format) if args.occurrences_of("format") == 0 && formats.contains(&&**format) => {
+                format
+            }
+            _ => args.value_of("format").unwrap(),
+        }
+    }
+
+    /// Like `filter::regex_values`, using the profile's regexes when none were given. Invalid
+    /// ones are skipped, `load_profile` has already refused them.
+    pub fn regex_values(&self, args: &ArgMatches<'_>, name: &str) -> Vec<Regex> {
+        let setting = match name {
+            "include" => &self.include,
+            "exclude" => &self.exclude,
+            _ => return crate::filter::regex_values(args, name),
+        };
+
+        if args.occurrences_of(name) > 0 || setting.is_empty() {
+            return crate::filter::regex_values(args, name);
+        }
+        setting.iter().filter_map(|i| Regex::new(i).ok()).collect()
+    }
+
+    pub fn depth(&self, args: &ArgMatches<'_>) -> Option<u8> {
+        match args.value_of("depth") {
+            Some(depth) => depth.parse().ok(),
+            None => self.depth,
+        }
+    }
+}
+
+/// A value given to `config set`, only the types profiles use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Array(Vec<String>),
+}
+
+impl Value {
+    fn string(self) -> Result<String, String> {
+        match self {
+            Value::String(value) => Ok(value),
+            _ => Err("expected a string".to_string()),
+        }
+    }
+
+    fn strings(self) -> Result<Vec<String>, String> {
+        match self {
+            Value::Array(values) => Ok(values),
+            Value::String(value) => Ok(vec![value]),
+            _ => Err("expected a list of strings".to_string()),
+        }
+    }
+}
+
+/// Run `command` through the shell and use the first line it prints as the token. Its
+/// stdin and stderr are left alone so password managers can ask to be unlocked.
+pub fn run_token_command(command: &str) -> Result<String, PagerDutyError> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+
+    let error = |message: String| PagerDutyError::TokenCommand {
+        command: command.to_string(),
+        message,
+    };
+    let output = shell
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| error(e.to_string()))?;
+    if !output.status.success() {
+        return Err(error(output.status.to_string()));
+    }
+
+    match String::from_utf8_lossy(&output.stdout).lines().next() {
+        Some(token) if !token.trim().is_empty() => Ok(token.trim().to_string()),
+        _ => Err(error("it printed nothing".to_string())),
+    }
+}
+
+fn config_error(path: &Path, message: String) -> PagerDutyError {
+    PagerDutyError::Config {
+        path: path.display().to_string(),
+        message,
+    }
+}
+
+/// Write a file only its owner can read.
+#[cfg(unix)]
+pub(crate) fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // `mode` only applies to new files.
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+/// Like `create_dir_all`, but only the owner can look inside `dir`.
+#[cfg(unix)]
+pub(crate) fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)?;
+    // `mode` only applies to new directories.
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)
+}
+
+/// `include` and `exclude` take a single regex as well as a list of them.
+fn string_or_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+
+    Ok(match StringOrList::deserialize(deserializer)? {
+        StringOrList::String(value) => vec![value],
+        StringOrList::List(values) => values,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+default_profile = "production" # used without --profile
+
+[profiles.production]
+token = "y_NbAkKc66ryYTWUXYEu"
+format = "json"
+include = ["^Payments", '\d+ # not a comment']
+depth = 2
+
+[profiles."sandbox account"]
+api_url = "https://api.eu.pagerduty.com"
+exclude = "^Test"
+"#;
+
+    #[test]
+    fn parses_profiles() {
+        let config = toml::from_str::<Config>(EXAMPLE).unwrap();
+
+        assert_eq!(Some("production".to_string()), config.default_profile);
+        let production = &config.profiles["production"];
+        assert_eq!(Some("y_NbAkKc66ryYTWUXYEu".to_string()), production.token);
+        assert_eq!(
+            vec!["^Payments".to_string(), "\\d+ # not a comment".to_string()],
+            production.include
+        );
+        assert_eq!(Some(2), production.depth);
+        assert_eq!(
+            vec!["^Test".to_string()],
+            config.profiles["sandbox account"].exclude
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let config: Config = toml::from_str(EXAMPLE).unwrap();
+        let written = toml::to_string(&config).unwrap();
+        assert_eq!(config, toml::from_str(&written).unwrap());
+    }
+
+    #[test]
+    fn reads_any_toml_layout() {
+        let config: Config = toml::from_str(
+            "[profiles]\nproduction = { depth = 1, include = [\n  \"^Payments\",\n  \"^Billing\",\n] }",
+        )
+        .unwrap();
+
+        let production = &config.profiles["production"];
+        assert_eq!(Some(1), production.depth);
+        assert_eq!(2, production.include.len());
+    }
+
+    #[test]
+    fn errors_name_the_setting() {
+        let error = toml::from_str::<Config>("[profiles.production]\ntokn = \"abc\"")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown field `tokn`"), "{}", error);
+        assert!(error.contains("profiles.production"), "{}", error);
+
+        assert!(toml::from_str::<Config>("[teams]").is_err());
+        assert!(toml::from_str::<Config>("[profiles.production]\ndepth = 300").is_err());
+    }
+
+    #[test]
+    fn validate_finds_bad_settings() {
+        let config: Config = toml::from_str(
+            "default_profile = \"missing\"\n[profiles.production]\nformat = \"yaml\"\ninclude = [\"(\"]",
+        )
+        .unwrap();
+
+        assert_eq!(3, config.validate().len());
+    }
+
+    #[test]
+    fn unknown_profiles_are_not_found() {
+        let config = toml::from_str::<Config>(EXAMPLE).unwrap();
+
+        assert_eq!(config.profiles["production"], config.profile(None).unwrap());
+        assert!(matches!(
+            config.profile(Some("staging")),
+            Err(PagerDutyError::NotFound {
+                kind: "profile",
+                ..
+            })
+        ));
+        assert_eq!(Profile::default(), Config::default().profile(None).unwrap());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn token_command_supplies_the_token() {
+        let profile = Profile {
+            token_command: Some("echo ' from-command '; echo second-line".into()),
+            ..Profile::default()
+        };
+        assert_eq!(
+            Some("from-command".to_string()),
+            profile.resolve_token().unwrap()
+        );
+
+        let failing = Profile {
+            token_command: Some("exit 3".into()),
+            ..Profile::default()
+        };
+        assert!(matches!(
+            failing.resolve_token(),
+            Err(PagerDutyError::TokenCommand { .. })
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::Duration;
+
+/// Durations are added to the current time, so they have to stay well inside what a `DateTime`
+/// can hold. Nothing in PagerDuty needs to last longer.
+const MAX_YEARS: i64 = 100;
+const MAX_SECONDS: i64 = MAX_YEARS * 365 * 24 * 60 * 60;
+
+/// Parse a human duration such as `30m`, `2h`, `1h30m` or `1d`. A bare number is seconds.
+/// It has to be longer than nothing.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("duration can not be empty".to_string());
+    }
+
+    let total_seconds = match input.parse::<i64>() {
+        Ok(seconds) => seconds,
+        Err(_) => parse_units(input)?,
+    };
+
+    if total_seconds <= 0 {
+        return Err(format!("`{}` has to be longer than 0 seconds", input));
+    }
+    if total_seconds > MAX_SECONDS {
+        return Err(format!(
+            "`{}` is too long, it can be at most {} years",
+            input, MAX_YEARS
+        ));
+    }
+
+    Ok(Duration::seconds(total_seconds))
+}
+
+fn parse_units(input: &str) -> Result<i64, String> {
+    let not_a_duration = || format!("`{}` is not a duration (like 30m, 2h or 1h30m)", input);
+
+    let mut total_seconds: i64 = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let value = number.parse::<i64>().map_err(|_| not_a_duration())?;
+        number.clear();
+
+        let unit_seconds = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(not_a_duration()),
+        };
+        total_seconds = value
+            .checked_mul(unit_seconds)
+            .and_then(|seconds| total_seconds.checked_add(seconds))
+            .ok_or_else(|| format!("`{}` is too long", input))?;
+    }
+
+    if !number.is_empty() {
+        return Err(format!(
+            "`{}` is missing a unit at the end (s, m, h, d or w)",
+            input
+        ));
+    }
+
+    Ok(total_seconds)
+}
+
+/// Format a duration the way it would be typed, largest units first, like `1d 12h` or `30m`.
+pub fn format_duration(duration: Duration) -> String {
+    let mut remaining = duration.num_seconds().abs();
+    let mut parts = Vec::new();
+
+    for (unit, unit_seconds) in &[
+        ("w", 7 * 24 * 60 * 60),
+        ("d", 24 * 60 * 60),
+        ("h", 60 * 60),
+        ("m", 60),
+    ] {
+        let value = remaining / unit_seconds;
+        if value > 0 {
+            parts.push(format!("{}{}", value, unit));
+            remaining -= value * unit_seconds;
+        }
+    }
+
+    if parts.is_empty() {
+        parts.push(format!("{}s", remaining));
+    }
+
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_simple_and_compound_durations() {
+        assert_eq!(Duration::minutes(30), parse_duration("30m").unwrap());
+        assert_eq!(Duration::hours(2), parse_duration("2h").unwrap());
+        assert_eq!(Duration::minutes(90), parse_duration("1h30m").unwrap());
+        assert_eq!(Duration::days(1), parse_duration("1d").unwrap());
+        assert_eq!(Duration::seconds(45), parse_duration("45").unwrap());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("2x").is_err());
+        assert!(parse_duration("1h30").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_that_are_not_positive_or_too_long() {
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("-30").is_err());
+        assert!(parse_duration("99999999999999w").is_err());
+        assert!(parse_duration("9223372036854775807").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_that_overflow_a_timestamp() {
+        assert!(parse_duration("100000000w").is_err());
+        assert!(parse_duration("5214w").is_ok());
+        assert!(parse_duration("5215w").is_err());
+    }
+
+    #[test]
+    fn formats_largest_units_first() {
+        assert_eq!("1w", format_duration(Duration::weeks(1)));
+        assert_eq!("1d 12h", format_duration(Duration::hours(36)));
+        assert_eq!("1h 30m", format_duration(Duration::minutes(90)));
+        assert_eq!("45s", format_duration(Duration::seconds(45)));
+    }
+}
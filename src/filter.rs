@@ -0,0 +1,163 @@
+use clap::ArgMatches;
+use regex::{Regex, RegexBuilder};
+
+use crate::v2::{PagerDutyClient, PagerDutyError, Service};
+
+enum NameMatch {
+    Yes,
+    No,
+    NotProvided,
+}
+
+/// Collect every value of a `+multiple` regex arg. Invalid expressions are a usage error.
+pub fn regex_values(args: &ArgMatches<'_>, name: &str) -> Vec<Regex> {
+    args.values_of(name)
+        .unwrap_or_default()
+        .map(|i| Regex::new(i).unwrap_or_else(|_| panic!("`{}` to be valid regex", i)))
+        .collect()
+}
+
+/// Includes take precedence, when none are provided anything not excluded is kept.
+pub fn should_be_included(includes_vec: &[Regex], excludes_vec: &[Regex], name: &str) -> bool {
+    match does_name_match(includes_vec, name) {
+        NameMatch::Yes => return true,
+        NameMatch::No => return false,
+        NameMatch::NotProvided => {}
+    };
+
+    match does_name_match(excludes_vec, name) {
+        NameMatch::Yes => false,
+        NameMatch::No => true,
+        NameMatch::NotProvided => true,
+    }
+}
+
+/// IDs of the teams given with the global `--team`, empty when there were none.
+pub async fn team_ids(
+    client: &PagerDutyClient,
+    args: &ArgMatches<'_>,
+) -> Result<Vec<String>, PagerDutyError> {
+    match args.values_of("team") {
+        Some(teams) => client.find_team_ids(&teams.collect::<Vec<&str>>()).await,
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Whether something owned by `teams` belongs to one of `team_ids`. Everything does when
+/// no team was asked for.
+pub fn is_owned_by(team_ids: &[String], teams: &[String]) -> bool {
+    team_ids.is_empty() || teams.iter().any(|team| team_ids.contains(team))
+}
+
+/// Whether a policy covering `services` covers one matching `services_vec`. Every policy
+/// does when no expression was given.
+pub fn covers_service(services_vec: &[Regex], services: &[String]) -> bool {
+    services_vec.is_empty()
+        || services
+            .iter()
+            .any(|service| matches!(does_name_match(services_vec, service), NameMatch::Yes))
+}
+
+/// The service with `query` as its ID or name, ignoring case. When there's none, every
+/// service with a name matching `query` as a case-insensitive regex.
+pub fn services_named(services: Vec<Service>, query: &str) -> Vec<Service> {
+    let (exact, others): (Vec<Service>, Vec<Service>) = services
+        .into_iter()
+        .partition(|service| service.id == query || service.name.eq_ignore_ascii_case(query));
+    if !exact.is_empty() {
+        return exact;
+    }
+
+    match RegexBuilder::new(query).case_insensitive(true).build() {
+        Ok(regex) => others
+            .into_iter()
+            .filter(|service| regex.is_match(&service.name))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn does_name_match(inputs: &[Regex], name: &str) -> NameMatch {
+    if !inputs.is_empty() {
+        for exp in inputs {
+            if exp.is_match(name) {
+                return NameMatch::Yes;
+            }
+        }
+        return NameMatch::No;
+    }
+
+    NameMatch::NotProvided
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn include_takes_precedence() {
+        let includes = vec![Regex::new("^Pay").unwrap()];
+        let excludes = vec![Regex::new("ments$").unwrap()];
+
+        assert!(should_be_included(&includes, &excludes, "Payments"));
+        assert!(!should_be_included(&includes, &excludes, "Checkout"));
+    }
+
+    #[test]
+    fn exclude_only() {
+        let excludes = vec![Regex::new("sandbox").unwrap()];
+
+        assert!(should_be_included(&[], &excludes, "Payments"));
+        assert!(!should_be_included(&[], &excludes, "sandbox-payments"));
+        assert!(should_be_included(&[], &[], "anything"));
+    }
+
+    #[test]
+    fn no_teams_keeps_everything() {
+        let payments = vec!["PQ9K7I8".to_string()];
+
+        assert!(is_owned_by(&[], &[]));
+        assert!(is_owned_by(
+            &payments,
+            &["PGVEH2X".into(), "PQ9K7I8".into()]
+        ));
+        assert!(!is_owned_by(&payments, &["PGVEH2X".into()]));
+        assert!(!is_owned_by(&payments, &[]));
+    }
+
+    #[test]
+    fn policies_are_kept_by_the_services_they_cover() {
+        let checkout = vec![Regex::new("^checkout-").unwrap()];
+
+        assert!(covers_service(&[], &[]));
+        assert!(covers_service(
+            &checkout,
+            &["payments-api".into(), "checkout-api".into()]
+        ));
+        assert!(!covers_service(&checkout, &["payments-api".into()]));
+        assert!(!covers_service(&checkout, &[]));
+    }
+
+    #[test]
+    fn exact_service_names_win_over_regex_matches() {
+        let services = vec![
+            Service::new("PX01HJD", "checkout-api"),
+            Service::new("PC8O0L3", "checkout-api-worker"),
+        ];
+
+        let names = |query: &str| -> Vec<String> {
+            services_named(services.clone(), query)
+                .into_iter()
+                .map(|service| service.name)
+                .collect()
+        };
+
+        assert_eq!(vec!["checkout-api"], names("Checkout-API"));
+        assert_eq!(vec!["checkout-api-worker"], names("PC8O0L3"));
+        assert_eq!(
+            vec!["checkout-api", "checkout-api-worker"],
+            names("^checkout")
+        );
+        assert!(names("(").is_empty());
+    }
+}
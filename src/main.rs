@@ -1,16 +1,21 @@
 use lazy_static::lazy_static;
 use std::sync::Mutex;
 
+use chrono::{DateTime, Utc};
 use clap::{clap_app, crate_version, ArgMatches};
 use dotenv::dotenv;
 use flexi_logger::{LevelFilter, LogSpecBuilder, Logger};
-use regex::Regex;
+use log::error;
 
+mod commands;
+mod config;
+mod duration;
+mod filter;
 mod output;
 mod progress;
 mod v2;
 
-use crate::v2::EscalationPolicy;
+use crate::v2::PagerDutyError;
 
 lazy_static! {
     static ref DEBUG_LEVEL: Mutex<i32> = Mutex::new(0);
@@ -32,6 +37,16 @@ async fn main() -> Result<(), &'static str> {
         (@setting ColorAuto)
         (@setting VersionlessSubcommands)
         (@arg API_TOKEN: -a --("api-token") +global +takes_value env("PAGERDUTY_TOKEN") "A PagerDuty API Token to valid for READ access")
+        (@arg API_URL: --("api-url") +global +takes_value env("PAGERDUTY_API_URL") default_value("https://api.pagerduty.com") "Base URL of the PagerDuty REST API. Useful for proxies, other regions or a local mock server.")
+        (@arg max_attempts: --("max-attempts") +global +takes_value default_value("5") {is_number} "How many times a request to PagerDuty is attempted before giving up.")
+        (@arg max_concurrent_requests: --("max-concurrent-requests") +global +takes_value default_value("4") {is_number} "How many requests may be sent to PagerDuty at the same time.")
+        (@arg profile: -p --profile +global +takes_value env("PAGERDUTY_PROFILE") "Profile from the config file to use. Command line arguments and environment variables take precedence.")
+        (@arg config: --config +global +takes_value env("PAGERDUTY_CONFIG") "Path to the config file. Defaults to $XDG_CONFIG_HOME/pagerduty-cli/config.toml or ~/.config/pagerduty-cli/config.toml.")
+        (@arg no_cache: --("no-cache") +global conflicts_with[refresh offline] "Don't read or save cached responses.")
+        (@arg refresh: --refresh +global conflicts_with[offline] "Fetch everything from PagerDuty, updating the cache.")
+        (@arg offline: --offline +global "Only use cached responses, however old, without talking to PagerDuty.")
+        (@arg team: -t --team +global +takes_value +multiple number_of_values(1) "Name or ID of a team, only list what it owns. Can be given more than once.")
+        (@arg cache_dir: --("cache-dir") +global +takes_value env("PAGERDUTY_CACHE_DIR") "Where responses are cached. Defaults to $XDG_CACHE_HOME/pagerduty-cli or ~/.cache/pagerduty-cli.")
         (@group logging =>
             (@arg debug: -v --verbose +global +multiple "Increasing verbosity")
             (@arg warn: -w --warn +global "Only display warning messages")
@@ -46,14 +61,59 @@ async fn main() -> Result<(), &'static str> {
             (@arg exclude: -x --exclude +takes_value +multiple "Regex that when matches will exclude the policy. Include takes precedence.  Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
             (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json csv] "Format the Escalation oncalls should be exported.")
             (@arg depth: --depth +takes_value {is_number} "How far down the Escalation Policy should be printed?")
+            (@arg at: --at +takes_value {commands::is_timestamp} conflicts_with[since until] "Show who was on call at this time instead of now.")
+            (@arg since: --since +takes_value {commands::is_timestamp} requires[until] "Show every shift from this time. Requires --until.")
+            (@arg until: --until +takes_value {commands::is_timestamp} requires[since] "Show every shift until this time. Requires --since.")
+            (@arg user: -u --user +takes_value conflicts_with[at since until] "Only show this user, by ID, email or `me`, along with their upcoming shifts.")
+            (@arg upcoming: --upcoming +takes_value {is_number} default_value("5") "How many upcoming shifts to show with --user.")
+            (@arg service: -s --service +takes_value +multiple conflicts_with[user] "Regex that when matches a service will include the policies covering it. Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+            (@arg show_services: --("show-services") conflicts_with[user] "List the services each escalation policy covers.")
         )
         (@subcommand export =>
             (name: "export")
             (about: "Export escalation policy to disk")
             (@arg dest: -o --output +takes_value default_value("-") "Where to save the output. Use `-` for stdout.")
-            (@arg format: -f --filter +takes_value possible_value[tfstate] "Only show Escalation Policies that contain the string.")
+            (@arg include: -i --include alias("filter") +takes_value +multiple "Regex that when matches will include the policy. Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+            (@arg exclude: -x --exclude +takes_value +multiple "Regex that when matches will exclude the policy. Include takes precedence.  Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+            (@arg format: -f --format +takes_value default_value("tfstate") possible_value[tfstate json yaml csv terraform] "`tfstate` for escalation policy IDs, `json`, `yaml` or `csv` for their rules, or `terraform` for the whole account as HCL with import blocks.")
+            (@arg dedupe: --dedupe +takes_value default_value("omit") possible_values(&["omit", "suffix-id", "first", "error"]) "How tfstate handles escalation policies sharing a name: leave them out, add the ID to each name, keep the lowest ID, or fail.")
+        )
+        (@subcommand incidents =>
+            (name: "incidents")
+            (about: "List incidents")
+            (@arg status: --status +takes_value +multiple possible_value[triggered acknowledged resolved] "Only list incidents with this status.")
+            (@arg urgency: --urgency +takes_value +multiple possible_value[high low] "Only list incidents with this urgency.")
+            (@arg service: -s --service +takes_value +multiple "Regex that when matches the service name will include the incident. Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+            (@arg exclude_service: -x --("exclude-service") +takes_value +multiple "Regex that when matches the service name will exclude the incident. Service takes precedence.")
+            (@arg since: --since +takes_value {commands::is_timestamp} "Only list incidents created after this time.")
+            (@arg until: --until +takes_value {commands::is_timestamp} "Only list incidents created before this time.")
+            (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json csv] "Format the incidents should be displayed in.")
         )
-    ).get_matches();
+        (@subcommand schedules =>
+            (name: "schedules")
+            (about: "Show schedule layers and who is on call over a time window")
+            (@arg include: -i --include +takes_value +multiple "Regex that when matches will include the schedule. Regex syntax: https://docs.rs/regex/1.3.6/regex/#syntax")
+            (@arg exclude: -x --exclude +takes_value +multiple "Regex that when matches will exclude the schedule. Include takes precedence.")
+            (@arg since: --since +takes_value {commands::is_timestamp} "Start of the window to render. Defaults to the start of the current hour, so runs within the hour share a cached response.")
+            (@arg until: --until +takes_value {commands::is_timestamp} "End of the window to render. Defaults to a week after since.")
+            (@arg format: -f --format +takes_value default_value("tree") possible_value[tree json csv] "Format the schedules should be displayed in.")
+        )
+    )
+    .subcommand(commands::incident::subcommand())
+    .subcommand(commands::overrides::subcommand())
+    .subcommand(commands::maintenance::subcommand())
+    .subcommand(commands::event::subcommand())
+    .subcommand(commands::event::change_subcommand())
+    .subcommand(commands::config::subcommand())
+    .subcommand(commands::login::subcommand())
+    .subcommand(commands::login::logout_subcommand())
+    .subcommand(commands::whoami::subcommand())
+    .subcommand(commands::whoami::abilities_subcommand())
+    .subcommand(commands::teams::subcommand())
+    .subcommand(commands::services::subcommand())
+    .subcommand(commands::page_who::subcommand())
+    .subcommand(commands::users::subcommand())
+    .get_matches();
 
     let level_filter = match (
         matches.is_present("quite"),
@@ -75,59 +135,226 @@ async fn main() -> Result<(), &'static str> {
         .start()
         .unwrap();
 
-    let pagerduty_client = v2::PagerDutyClient::new(matches.value_of("API_TOKEN").unwrap());
+    let request_options = v2::RequestOptions {
+        max_attempts: matches
+            .value_of("max_attempts")
+            .map(|s| s.parse::<u32>().unwrap())
+            .unwrap(),
+        max_concurrent_requests: matches
+            .value_of("max_concurrent_requests")
+            .map(|s| s.parse::<usize>().unwrap())
+            .unwrap(),
+        cache: v2::CacheOptions {
+            mode: if matches.is_present("no_cache") {
+                v2::CacheMode::Disabled
+            } else if matches.is_present("refresh") {
+                v2::CacheMode::Refresh
+            } else if matches.is_present("offline") {
+                v2::CacheMode::Offline
+            } else {
+                v2::CacheMode::Normal
+            },
+            dir: matches
+                .value_of("cache_dir")
+                .map(std::path::PathBuf::from)
+                .or_else(v2::CacheOptions::default_dir),
+        },
+    };
+
+    let config_path = matches
+        .value_of("config")
+        .map(std::path::PathBuf::from)
+        .or_else(config::default_path);
 
-    match matches.subcommand() {
+    // `config` has to work even when the file is broken, so it can be checked.
+    let profile = match (matches.subcommand_name(), &config_path) {
+        (Some("config"), _) | (_, None) => config::Profile::default(),
+        (command, Some(path)) => match config::load_profile(path, matches.value_of("profile")) {
+            Ok(profile) => profile,
+            // Logging in is how a new profile gets a token.
+            Err(PagerDutyError::NotFound { .. }) if command == Some("login") => {
+                config::Profile::default()
+            }
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(e.exit_code());
+            }
+        },
+    };
+
+    // Flags and environment variables win over the profile, which wins over clap's defaults.
+    let api_url = match &profile.api_url {
+        Some(api_url)
+            if matches.occurrences_of("API_URL") == 0
+                && std::env::var_os("PAGERDUTY_API_URL").is_none() =>
+        {
+            api_url.as_str()
+        }
+        _ => matches.value_of("API_URL").unwrap(),
+    };
+
+    // The Events API authenticates with a routing key, so a token is only needed for the REST API.
+    // `login` and `logout` deal with the saved token themselves.
+    let needs_token = !matches!(
+        matches.subcommand_name(),
+        Some("event") | Some("change-event") | Some("config") | Some("login") | Some("logout")
+    );
+    let auth_token = match matches.value_of("API_TOKEN") {
+        Some(token) => token.to_string(),
+        None if needs_token => match profile.resolve_token() {
+            Ok(token) => token.unwrap_or_default(),
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(e.exit_code());
+            }
+        },
+        None => String::new(),
+    };
+    let pagerduty_client = v2::PagerDutyClient::new(&auth_token, api_url, request_options.clone());
+
+    let result = match matches.subcommand() {
         ("who-is-oncall", Some(arg_matches)) => {
-            who_is_oncall(pagerduty_client, &arg_matches).await;
+            who_is_oncall(pagerduty_client, &profile, arg_matches).await
         }
         ("export", Some(arg_matches)) => {
-            export_escilation_policies(pagerduty_client, &arg_matches).await;
+            export_escilation_policies(pagerduty_client, &profile, arg_matches).await
+        }
+        ("incidents", Some(arg_matches)) => {
+            list_incidents(pagerduty_client, &profile, arg_matches).await
+        }
+        ("schedules", Some(arg_matches)) => {
+            list_schedules(pagerduty_client, &profile, arg_matches).await
+        }
+        ("incident", Some(arg_matches)) => {
+            commands::incident::run(pagerduty_client, arg_matches).await
+        }
+        ("override", Some(arg_matches)) => {
+            commands::overrides::run(pagerduty_client, &profile, arg_matches).await
+        }
+        ("maintenance", Some(arg_matches)) => {
+            commands::maintenance::run(pagerduty_client, &profile, arg_matches).await
+        }
+        ("event", Some(arg_matches)) => commands::event::run(request_options, arg_matches).await,
+        ("change-event", Some(arg_matches)) => {
+            commands::event::run_change(request_options, arg_matches).await
+        }
+        ("config", Some(arg_matches)) => commands::config::run(config_path, arg_matches),
+        ("login", Some(arg_matches)) => {
+            commands::login::run(config_path, api_url, request_options, arg_matches).await
+        }
+        ("logout", Some(arg_matches)) => commands::login::run_logout(config_path, arg_matches),
+        ("teams", Some(arg_matches)) => {
+            commands::teams::run(pagerduty_client, &profile, arg_matches).await
+        }
+        ("services", Some(arg_matches)) => {
+            commands::services::run(pagerduty_client, &profile, arg_matches).await
+        }
+        ("page-who", Some(arg_matches)) => {
+            commands::page_who::run(pagerduty_client, &profile, arg_matches).await
+        }
+        ("users", Some(arg_matches)) => {
+            commands::users::run(pagerduty_client, &profile, arg_matches).await
+        }
+        ("whoami", Some(arg_matches)) => {
+            commands::whoami::run(pagerduty_client, &profile, arg_matches).await
+        }
+        ("abilities", Some(arg_matches)) => {
+            commands::whoami::run_abilities(pagerduty_client, arg_matches).await
         }
         _ => unreachable!(),
     };
 
+    if let Err(e) = result {
+        error!("{}", e);
+        std::process::exit(e.exit_code());
+    }
+
     Ok(())
 }
 
-async fn export_escilation_policies(client: v2::PagerDutyClient, args: &ArgMatches<'_>) {
-    let policies = client.fetch_policies_for_account().await;
-    let mut tf_state = output::tfstate::TfStateExportData::default();
+async fn export_escilation_policies(
+    client: v2::PagerDutyClient,
+    profile: &config::Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let include_vec = profile.regex_values(args, "include");
+    let exclude_vec = profile.regex_values(args, "exclude");
+    let dest = args.value_of("dest").unwrap();
+    // Not the profile's format, `export` feeds Terraform so it only changes when asked to.
+    let format = args.value_of("format").unwrap();
+    let team_ids = filter::team_ids(&client, args).await?;
 
-    for policy in policies {
-        tf_state.add_escalation_policy(policy);
+    if format == "terraform" {
+        let mut account = client.fetch_account().await?;
+        account.escalation_policies.retain(|policy| {
+            filter::should_be_included(&include_vec, &exclude_vec, &policy.policy_name)
+                && filter::is_owned_by(&team_ids, &policy.teams)
+        });
+        if !team_ids.is_empty() {
+            account
+                .services
+                .retain(|service| filter::is_owned_by(&team_ids, &service.teams));
+            account
+                .schedules
+                .retain(|schedule| filter::is_owned_by(&team_ids, &schedule.teams));
+            account
+                .users
+                .retain(|user| filter::is_owned_by(&team_ids, &user.teams));
+            account.teams.retain(|team| team_ids.contains(&team.id));
+        }
+        let output = output::terraform::build_terraform_output(&account);
+        output::write_file(dest, &output).ok();
+        return Ok(());
     }
 
-    let dest = args.value_of("dest").unwrap();
-    let output = serde_json::to_string_pretty(&tf_state).unwrap();
+    let policies: Vec<_> = client
+        .fetch_policies_for_account(None)
+        .await?
+        .into_iter()
+        .filter(|policy| {
+            filter::should_be_included(&include_vec, &exclude_vec, &policy.policy_name)
+                && filter::is_owned_by(&team_ids, &policy.teams)
+        })
+        .collect();
+
+    let output = match format {
+        "json" => output::export::build_json_output(policies),
+        "yaml" => output::export::build_yaml_output(policies),
+        "csv" => output::export::build_csv_output(policies),
+        "tfstate" => build_tfstate_output(policies, args)?,
+        _ => unreachable!(),
+    };
+
     output::write_file(dest, &output).ok();
+
+    Ok(())
 }
 
-async fn who_is_oncall(client: v2::PagerDutyClient, args: &ArgMatches<'_>) {
-    let include_vec: Vec<Regex> = args
-        .values_of("include")
-        .unwrap_or_default()
-        .map(|i| Regex::new(i).unwrap_or_else(|_| panic!("`{}` to be valid regex", i)))
-        .collect();
+fn build_tfstate_output(
+    policies: Vec<v2::EscalationPolicy>,
+    args: &ArgMatches<'_>,
+) -> Result<String, PagerDutyError> {
+    let strategy = match args.value_of("dedupe").unwrap() {
+        "omit" => output::tfstate::DedupeStrategy::Omit,
+        "suffix-id" => output::tfstate::DedupeStrategy::SuffixId,
+        "first" => output::tfstate::DedupeStrategy::First,
+        "error" => output::tfstate::DedupeStrategy::Error,
+        _ => unreachable!(),
+    };
 
-    let exclude_vec: Vec<Regex> = args
-        .values_of("exclude")
-        .unwrap_or_default()
-        .map(|i| Regex::new(i).unwrap_or_else(|_| panic!("`{}` to be valid regex", i)))
-        .collect();
+    let tf_state = output::tfstate::TfStateExportData::from_policies(policies, strategy)?;
+    Ok(serde_json::to_string_pretty(&tf_state).unwrap())
+}
 
-    let max_depth = args
-        .value_of("depth")
-        .map(|s| s.parse::<u8>().unwrap())
-        .unwrap_or(255);
+async fn who_is_oncall(
+    client: v2::PagerDutyClient,
+    profile: &config::Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let include_vec = profile.regex_values(args, "include");
+    let exclude_vec = profile.regex_values(args, "exclude");
 
-    let mut policies = Vec::new();
-    for policy in client.fetch_policies_for_account().await {
-        if policy_should_be_included(&include_vec, &exclude_vec, &policy) {
-            policies.push(policy);
-        }
-    }
-    policies.sort();
+    let max_depth = profile.depth(args).unwrap_or(255);
 
     let usergroup_filter = |usergroup: &crate::v2::PagerDutyUserGroups| {
         if usergroup.depth > max_depth {
@@ -136,51 +363,249 @@ async fn who_is_oncall(client: v2::PagerDutyClient, args: &ArgMatches<'_>) {
         true
     };
 
-    let output = match args.value_of("format").unwrap() {
-        "tree" => output::build_tree_output(policies, usergroup_filter),
-        "json" => output::build_json_output(policies, usergroup_filter),
-        "csv" => output::build_csv_output(policies, usergroup_filter),
+    if let Some(user) = args.value_of("user") {
+        let upcoming = args.value_of("upcoming").unwrap().parse::<usize>().unwrap();
+        return user_oncalls(client, profile, args, user, upcoming, usergroup_filter).await;
+    }
+
+    let window = match (
+        args.value_of("at"),
+        args.value_of("since"),
+        args.value_of("until"),
+    ) {
+        (Some(at), _, _) => {
+            let at = DateTime::parse_from_rfc3339(at)
+                .unwrap()
+                .with_timezone(&Utc);
+            Some(v2::OncallWindow {
+                since: at.to_rfc3339(),
+                until: (at + chrono::Duration::seconds(1)).to_rfc3339(),
+            })
+        }
+        (None, Some(since), Some(until)) => Some(v2::OncallWindow {
+            since: since.to_string(),
+            until: until.to_string(),
+        }),
+        _ => None,
+    };
+
+    let team_ids = filter::team_ids(&client, args).await?;
+    let service_vec = filter::regex_values(args, "service");
+    let show_services = args.is_present("show_services");
+
+    let mut policies = Vec::new();
+    for policy in client.fetch_policies_for_account(window.as_ref()).await? {
+        if filter::should_be_included(&include_vec, &exclude_vec, &policy.policy_name)
+            && filter::is_owned_by(&team_ids, &policy.teams)
+            && filter::covers_service(&service_vec, &policy.services)
+        {
+            policies.push(policy);
+        }
+    }
+    policies.sort();
+
+    let output = match profile.format(args, &config::FORMATS) {
+        "tree" if window.is_some() => {
+            output::build_shift_tree_output(policies, usergroup_filter, show_services)
+        }
+        "tree" => output::build_tree_output(policies, usergroup_filter, show_services),
+        "json" => output::build_json_output(policies, usergroup_filter, show_services),
+        "csv" => output::build_csv_output(policies, usergroup_filter, show_services),
         _ => unreachable!(),
     };
 
     println!("{}", output);
-}
 
-enum PolicyMatch {
-    Yes,
-    No,
-    NotProvided,
+    Ok(())
 }
 
-fn policy_should_be_included(
-    includes_vec: &[Regex],
-    excludes_vec: &[Regex],
-    policy: &EscalationPolicy,
-) -> bool {
-    match does_policy_match(includes_vec, &policy) {
-        PolicyMatch::Yes => return true,
-        PolicyMatch::No => return false,
-        PolicyMatch::NotProvided => {}
-    };
-
-    match does_policy_match(excludes_vec, &policy) {
-        PolicyMatch::Yes => false,
-        PolicyMatch::No => true,
-        PolicyMatch::NotProvided => true,
+/// Everything `user` is on call for now, followed by their next `upcoming` shifts.
+async fn user_oncalls<P>(
+    client: v2::PagerDutyClient,
+    profile: &config::Profile,
+    args: &ArgMatches<'_>,
+    user: &str,
+    upcoming: usize,
+    usergroup_filter: P,
+) -> Result<(), PagerDutyError>
+where
+    P: FnMut(&crate::v2::PagerDutyUserGroups) -> bool + Copy,
+{
+    let include_vec = profile.regex_values(args, "include");
+    let exclude_vec = profile.regex_values(args, "exclude");
+
+    let user = client.find_user(user).await?;
+
+    let now = Utc::now();
+    let window = v2::OncallWindow {
+        since: v2::cacheable_now("oncalls").to_rfc3339(),
+        until: (now + chrono::Duration::days(30)).to_rfc3339(),
+    };
+
+    let (current, future) = futures::join!(
+        client.fetch_oncalls_for_user(&user, None),
+        client.fetch_oncalls_for_user(&user, Some(&window))
+    );
+
+    let current: Vec<_> = current?
+        .into_iter()
+        .filter(|policy| {
+            filter::should_be_included(&include_vec, &exclude_vec, &policy.policy_name)
+        })
+        .collect();
+
+    let starts_after_now = |user: &v2::PagerDutyUser| {
+        user.shift_start
+            .as_deref()
+            .and_then(|start| DateTime::parse_from_rfc3339(start).ok())
+            .map(|start| start > now)
+            .unwrap_or(false)
+    };
+
+    let mut future: Vec<_> = future?
+        .into_iter()
+        .filter(|policy| {
+            filter::should_be_included(&include_vec, &exclude_vec, &policy.policy_name)
+        })
+        .collect();
+
+    let mut starts: Vec<DateTime<Utc>> = future
+        .iter()
+        .flat_map(|policy| policy.oncall_groups.iter())
+        .flat_map(|group| group.users.iter())
+        .filter(|user| starts_after_now(user))
+        .filter_map(|user| user.shift_start.as_deref())
+        .filter_map(|start| DateTime::parse_from_rfc3339(start).ok())
+        .map(|start| start.with_timezone(&Utc))
+        .collect();
+    starts.sort();
+    starts.dedup();
+    starts.truncate(upcoming);
+
+    let is_upcoming = |user: &v2::PagerDutyUser| {
+        user.shift_start
+            .as_deref()
+            .and_then(|start| DateTime::parse_from_rfc3339(start).ok())
+            .map(|start| starts.contains(&start.with_timezone(&Utc)))
+            .unwrap_or(false)
+    };
+
+    for policy in future.iter_mut() {
+        for group in policy.oncall_groups.iter_mut() {
+            group.users.retain(|user| is_upcoming(user));
+        }
+        policy.oncall_groups.retain(|group| !group.users.is_empty());
     }
+    future.retain(|policy| !policy.oncall_groups.is_empty());
+
+    let output = match profile.format(args, &config::FORMATS) {
+        "tree" => format!(
+            "{}\nUpcoming\n{}",
+            output::build_tree_output(current, usergroup_filter, false),
+            output::build_shift_tree_output(future, usergroup_filter, false)
+        ),
+        "json" => output::build_json_output(
+            current.into_iter().chain(future).collect(),
+            usergroup_filter,
+            false,
+        ),
+        "csv" => output::build_csv_output(
+            current.into_iter().chain(future).collect(),
+            usergroup_filter,
+            false,
+        ),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
 }
 
-fn does_policy_match(inputs: &[Regex], policy: &EscalationPolicy) -> PolicyMatch {
-    if !inputs.is_empty() {
-        for exp in inputs {
-            if exp.is_match(&policy.policy_name) {
-                return PolicyMatch::Yes;
-            }
+async fn list_incidents(
+    client: v2::PagerDutyClient,
+    profile: &config::Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let include_vec = filter::regex_values(args, "service");
+    let exclude_vec = filter::regex_values(args, "exclude_service");
+
+    let query = v2::IncidentQuery {
+        statuses: args
+            .values_of("status")
+            .unwrap_or_default()
+            .map(String::from)
+            .collect(),
+        urgencies: args
+            .values_of("urgency")
+            .unwrap_or_default()
+            .map(String::from)
+            .collect(),
+        since: args.value_of("since").map(String::from),
+        until: args.value_of("until").map(String::from),
+        team_ids: filter::team_ids(&client, args).await?,
+    };
+
+    let mut incidents = Vec::new();
+    for incident in client.fetch_incidents(&query).await? {
+        if filter::should_be_included(&include_vec, &exclude_vec, &incident.service_name) {
+            incidents.push(incident);
         }
-        return PolicyMatch::No;
     }
 
-    PolicyMatch::NotProvided
+    let output = match profile.format(args, &config::FORMATS) {
+        "tree" => output::incident::build_tree_output(incidents),
+        "json" => output::incident::build_json_output(incidents),
+        "csv" => output::incident::build_csv_output(incidents),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
+
+async fn list_schedules(
+    client: v2::PagerDutyClient,
+    profile: &config::Profile,
+    args: &ArgMatches<'_>,
+) -> Result<(), PagerDutyError> {
+    let include_vec = filter::regex_values(args, "include");
+    let exclude_vec = filter::regex_values(args, "exclude");
+
+    let since = match args.value_of("since") {
+        Some(since) => DateTime::parse_from_rfc3339(since)
+            .unwrap()
+            .with_timezone(&Utc),
+        None => v2::cacheable_now("schedules"),
+    };
+    let until = match args.value_of("until") {
+        Some(until) => DateTime::parse_from_rfc3339(until)
+            .unwrap()
+            .with_timezone(&Utc),
+        None => since + chrono::Duration::weeks(1),
+    };
+
+    let team_ids = filter::team_ids(&client, args).await?;
+    let schedules: Vec<_> = client
+        .fetch_schedules(&since.to_rfc3339(), &until.to_rfc3339(), |name| {
+            filter::should_be_included(&include_vec, &exclude_vec, name)
+        })
+        .await?
+        .into_iter()
+        .filter(|schedule| filter::is_owned_by(&team_ids, &schedule.teams))
+        .collect();
+
+    let output = match profile.format(args, &config::FORMATS) {
+        "tree" => output::schedule::build_tree_output(schedules),
+        "json" => output::schedule::build_json_output(schedules),
+        "csv" => output::schedule::build_csv_output(schedules),
+        _ => unreachable!(),
+    };
+
+    println!("{}", output);
+
+    Ok(())
 }
 
 fn custom_log_format(
@@ -0,0 +1,178 @@
+use serde::Serialize;
+
+use crate::v2::EscalationPolicy;
+
+/// One row per rule target, so a policy with no rules still gets a row.
+pub fn build_csv_output(policies: Vec<EscalationPolicy>) -> String {
+    let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
+
+    wtr.write_record([
+        "Escalation Policy ID",
+        "Escalation Policy",
+        "Loops",
+        "Level",
+        "Delay (minutes)",
+        "Target Type",
+        "Target ID",
+        "Services",
+    ])
+    .expect("To be able to write header");
+
+    for policy in policies {
+        let services = policy.services.join(", ");
+        let num_loops = policy.num_loops.to_string();
+
+        if policy.rules.is_empty() {
+            wtr.write_record([
+                &policy.id,
+                &policy.policy_name,
+                &num_loops,
+                "",
+                "",
+                "",
+                "",
+                &services,
+            ])
+            .expect("to be able to write row");
+        }
+
+        for (level, rule) in policy.rules.iter().enumerate() {
+            for target in &rule.targets {
+                wtr.write_record([
+                    &policy.id,
+                    &policy.policy_name,
+                    &num_loops,
+                    &(level + 1).to_string(),
+                    &rule.escalation_delay_in_minutes.to_string(),
+                    &target.target_type,
+                    &target.id,
+                    &services,
+                ])
+                .expect("to be able to write row");
+            }
+        }
+    }
+
+    String::from_utf8(wtr.into_inner().expect("to be able to get vec"))
+        .expect("To be able to serialize CSV")
+}
+
+pub fn build_json_output(policies: Vec<EscalationPolicy>) -> String {
+    serde_json::to_string_pretty(&policy_exports(policies)).expect("policies to serialize")
+}
+
+pub fn build_yaml_output(policies: Vec<EscalationPolicy>) -> String {
+    serde_yaml::to_string(&policy_exports(policies)).expect("policies to serialize")
+}
+
+/// What `json` and `yaml` export for a policy.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PolicyExport {
+    id: String,
+    name: String,
+    description: Option<String>,
+    num_loops: u32,
+    teams: Vec<String>,
+    services: Vec<String>,
+    rules: Vec<RuleExport>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RuleExport {
+    level: usize,
+    delay_minutes: u32,
+    targets: Vec<TargetExport>,
+}
+
+#[derive(Debug, Serialize)]
+struct TargetExport {
+    #[serde(rename = "type")]
+    target_type: String,
+    id: String,
+}
+
+fn policy_exports(policies: Vec<EscalationPolicy>) -> Vec<PolicyExport> {
+    policies
+        .into_iter()
+        .map(|policy| PolicyExport {
+            id: policy.id,
+            name: policy.policy_name,
+            description: policy.description,
+            num_loops: policy.num_loops,
+            teams: policy.teams,
+            services: policy.services,
+            rules: policy
+                .rules
+                .into_iter()
+                .enumerate()
+                .map(|(level, rule)| RuleExport {
+                    level: level + 1,
+                    delay_minutes: rule.escalation_delay_in_minutes,
+                    targets: rule
+                        .targets
+                        .into_iter()
+                        .map(|target| TargetExport {
+                            target_type: target.target_type,
+                            id: target.id,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v2::{EscalationRule, EscalationTarget};
+
+    fn policy() -> EscalationPolicy {
+        let mut policy = EscalationPolicy::new("P9OFD2O", "Default");
+        policy.description = Some("Pages \"everyone\"".into());
+        policy.services = vec!["checkout".into()];
+        policy.rules = vec![EscalationRule {
+            escalation_delay_in_minutes: 30,
+            targets: vec![EscalationTarget {
+                id: "PPC00ZX".into(),
+                target_type: "user_reference".into(),
+            }],
+        }];
+        policy
+    }
+
+    #[test]
+    fn yaml_reads_back_as_the_json_export() {
+        let mut tricky = EscalationPolicy::new("PTRICKY", "- null: true #1");
+        tricky.description = Some("0123\nsecond line: \"quoted\"".into());
+        tricky.teams = vec!["yes".into(), "".into()];
+        tricky.rules = vec![];
+        let policies = vec![policy(), tricky];
+
+        let yaml: serde_json::Value =
+            serde_yaml::from_str(&build_yaml_output(policies.clone())).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&build_json_output(policies)).unwrap();
+
+        assert_eq!(json, yaml);
+        assert_eq!("- null: true #1", yaml[1]["name"]);
+        assert_eq!("user_reference", yaml[0]["rules"][0]["targets"][0]["type"]);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_target() {
+        let mut empty = EscalationPolicy::new("PEMPTY1", "Empty");
+        empty.rules = vec![];
+
+        let output = build_csv_output(vec![policy(), empty]);
+        let rows: Vec<&str> = output.lines().collect();
+
+        assert_eq!(3, rows.len());
+        assert_eq!(
+            "P9OFD2O,Default,0,1,30,user_reference,PPC00ZX,checkout",
+            rows[1]
+        );
+        assert_eq!("PEMPTY1,Empty,0,,,,,", rows[2]);
+    }
+}
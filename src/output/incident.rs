@@ -0,0 +1,108 @@
+use json::object;
+
+use crate::v2::{Incident, IncidentActionResult};
+
+use super::tree;
+
+pub fn build_csv_output(incidents: Vec<Incident>) -> String {
+    let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
+
+    wtr.write_record([
+        "Incident ID",
+        "Incident Number",
+        "Title",
+        "Status",
+        "Urgency",
+        "Service",
+        "Escalation Policy",
+        "Created",
+        "Assignees",
+    ])
+    .expect("To be able to write header");
+
+    for incident in incidents {
+        wtr.write_record([
+            incident.id,
+            incident.incident_number.to_string(),
+            incident.title,
+            incident.status,
+            incident.urgency,
+            incident.service_name,
+            incident.escalation_policy_name,
+            incident.created_at,
+            incident.assignees.join(", "),
+        ])
+        .expect("to be able to write row");
+    }
+
+    String::from_utf8(wtr.into_inner().expect("to be able to get vec"))
+        .expect("To be able to serialize CSV")
+}
+
+pub fn build_json_output(incidents: Vec<Incident>) -> String {
+    let mut outputs = Vec::new();
+
+    for incident in incidents {
+        outputs.push(object! {
+            id: incident.id,
+            incidentNumber: incident.incident_number,
+            title: incident.title,
+            status: incident.status,
+            urgency: incident.urgency,
+            serviceId: incident.service_id,
+            serviceName: incident.service_name,
+            escalationPolicy: incident.escalation_policy_name,
+            createdAt: incident.created_at,
+            assignees: incident.assignees,
+            url: incident.html_url
+        });
+    }
+
+    json::stringify_pretty(outputs, 2)
+}
+
+pub fn build_tree_output(incidents: Vec<Incident>) -> String {
+    let tree = tree::TreePrinter::default();
+
+    for incident in incidents {
+        let root = tree.add_line(format!(
+            "Incident #{} - {}",
+            incident.incident_number, incident.title
+        ));
+        root.add_line(format!(
+            "Status - {} ({} urgency)",
+            incident.status, incident.urgency
+        ));
+        root.add_line(format!("Service - {}", incident.service_name));
+        root.add_line(format!("Created - {}", incident.created_at));
+        if !incident.assignees.is_empty() {
+            root.add_line(format!("Assigned - {}", incident.assignees.join(", ")));
+        }
+        root.add_line(format!("Link - {}", incident.html_url));
+    }
+
+    tree.render()
+}
+
+pub fn build_action_summary(results: &[IncidentActionResult]) -> String {
+    let mut lines = Vec::new();
+    let mut failures = 0;
+
+    for result in results {
+        match &result.result {
+            Ok(()) => lines.push(format!("{} - ok", result.incident_id)),
+            Err(e) => {
+                failures += 1;
+                lines.push(format!("{} - failed: {}", result.incident_id, e));
+            }
+        }
+    }
+
+    lines.push(format!(
+        "{} succeeded, {} failed",
+        results.len() - failures,
+        failures
+    ));
+
+    lines.join("\n")
+}
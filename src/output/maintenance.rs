@@ -0,0 +1,74 @@
+use json::object;
+
+use crate::v2::MaintenanceWindow;
+
+use super::tree;
+
+pub fn build_csv_output(windows: Vec<MaintenanceWindow>) -> String {
+    let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
+
+    wtr.write_record([
+        "Maintenance Window ID",
+        "Sequence Number",
+        "Start",
+        "End",
+        "Description",
+        "Services",
+    ])
+    .expect("To be able to write header");
+
+    for window in windows {
+        wtr.write_record([
+            window.id,
+            window.sequence_number.to_string(),
+            window.start_time,
+            window.end_time,
+            window.description.unwrap_or_default(),
+            window.services.join(", "),
+        ])
+        .expect("to be able to write row");
+    }
+
+    String::from_utf8(wtr.into_inner().expect("to be able to get vec"))
+        .expect("To be able to serialize CSV")
+}
+
+pub fn build_json_output(windows: Vec<MaintenanceWindow>) -> String {
+    let mut outputs = Vec::new();
+
+    for window in windows {
+        outputs.push(object! {
+            id: window.id,
+            sequenceNumber: window.sequence_number,
+            start: window.start_time,
+            end: window.end_time,
+            description: window.description,
+            url: window.html_url,
+            services: window.services
+        });
+    }
+
+    json::stringify_pretty(outputs, 2)
+}
+
+pub fn build_tree_output(windows: Vec<MaintenanceWindow>) -> String {
+    let tree = tree::TreePrinter::default();
+
+    for window in windows {
+        let sequence_number = window.sequence_number;
+        let root = tree.add_line(format!(
+            "Maintenance Window - {} ({})",
+            window
+                .description
+                .unwrap_or_else(|| format!("#{}", sequence_number)),
+            window.id
+        ));
+        root.add_line(format!("{} to {}", window.start_time, window.end_time));
+        let services = root.add_line("Services".into());
+        for service in window.services {
+            services.add_line(service);
+        }
+    }
+
+    tree.render()
+}
@@ -1,36 +1,67 @@
+pub mod export;
+pub mod incident;
+pub mod maintenance;
+pub mod page_who;
+pub mod schedule;
+pub mod service;
+pub mod team;
+pub mod terraform;
 pub mod tfstate;
 pub mod tree;
+pub mod user;
+pub mod whoami;
 
+use chrono::Utc;
 use json::object;
+use std::collections::BTreeMap;
 use std::fs;
 
-pub fn build_csv_output<P>(policies: Vec<crate::v2::EscalationPolicy>, mut filter: P) -> String
+/// `show_services` adds a column with the services each policy covers.
+pub fn build_csv_output<P>(
+    policies: Vec<crate::v2::EscalationPolicy>,
+    mut filter: P,
+    show_services: bool,
+) -> String
 where
     P: FnMut(&crate::v2::PagerDutyUserGroups) -> bool,
 {
     let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
 
-    wtr.write_record(&[
+    let mut header = vec![
         "Escalation Policy ID",
         "Escalation Policy",
         "depth",
         "name",
         "email",
-    ])
-    .expect("To be able to write header");
+        "shift start",
+        "shift end",
+        "schedule",
+    ];
+    if show_services {
+        header.push("services");
+    }
+    wtr.write_record(&header)
+        .expect("To be able to write header");
 
     for policy in policies {
+        let services = policy.services.join(", ");
         for group in policy.oncall_groups {
             if filter(&group) {
                 for user in group.users {
-                    wtr.write_record(&[
+                    let mut row = vec![
                         policy.id.clone(),
                         policy.policy_name.clone(),
                         group.depth.to_string(),
                         user.name,
                         user.email,
-                    ])
-                    .expect("to be able to write row");
+                        user.shift_start.unwrap_or_default(),
+                        user.shift_end.unwrap_or_default(),
+                        user.schedule.unwrap_or_default(),
+                    ];
+                    if show_services {
+                        row.push(services.clone());
+                    }
+                    wtr.write_record(&row).expect("to be able to write row");
                 }
             }
         }
@@ -40,7 +71,12 @@ where
         .expect("To be able to serialize CSV")
 }
 
-pub fn build_json_output<P>(policies: Vec<crate::v2::EscalationPolicy>, mut filter: P) -> String
+/// `show_services` adds a `services` array with the services each policy covers.
+pub fn build_json_output<P>(
+    policies: Vec<crate::v2::EscalationPolicy>,
+    mut filter: P,
+    show_services: bool,
+) -> String
 where
     P: FnMut(&crate::v2::PagerDutyUserGroups) -> bool,
 {
@@ -50,13 +86,23 @@ where
         for group in policy.oncall_groups {
             if filter(&group) {
                 for user in group.users {
-                    outputs.push(object! {
+                    let mut output = object! {
                         id: policy.id.clone(),
                         escalationPolicy: policy.policy_name.clone(),
                         depth: group.depth,
                         userName: user.name,
-                        userEmail: user.email
-                    });
+                        userEmail: user.email,
+                        shiftStart: user.shift_start,
+                        shiftEnd: user.shift_end,
+                        schedule: user.schedule,
+                        userTeams: user.teams
+                    };
+                    if show_services {
+                        output
+                            .insert("services", policy.services.clone())
+                            .expect("output to be an object");
+                    }
+                    outputs.push(output);
                 }
             }
         }
@@ -65,14 +111,22 @@ where
     json::stringify_pretty(outputs, 2)
 }
 
-pub fn build_tree_output<P>(policies: Vec<crate::v2::EscalationPolicy>, mut filter: P) -> String
+pub fn build_tree_output<P>(
+    policies: Vec<crate::v2::EscalationPolicy>,
+    mut filter: P,
+    show_services: bool,
+) -> String
 where
     P: FnMut(&crate::v2::PagerDutyUserGroups) -> bool,
 {
     let tree = tree::TreePrinter::default();
+    let now = Utc::now();
 
     for policy in policies {
         let root = tree.add_line(format!("Escilation Policy - {}", policy.policy_name));
+        if show_services {
+            add_services(&root, &policy.services);
+        }
         let oncalls = root.add_line("Oncalls".into());
         for group in policy.oncall_groups {
             if filter(&group) {
@@ -82,7 +136,7 @@ where
                     group
                         .users
                         .iter()
-                        .map(|user| user.to_display())
+                        .map(|user| format!("{} [{}]", user.to_display(), user.shift_display(now)))
                         .collect::<Vec<String>>()
                         .join(", ")
                 ));
@@ -93,6 +147,64 @@ where
     tree.render()
 }
 
+/// Like `build_tree_output`, but every level lists each shift in turn so handoffs are visible.
+pub fn build_shift_tree_output<P>(
+    policies: Vec<crate::v2::EscalationPolicy>,
+    mut filter: P,
+    show_services: bool,
+) -> String
+where
+    P: FnMut(&crate::v2::PagerDutyUserGroups) -> bool,
+{
+    let tree = tree::TreePrinter::default();
+
+    for policy in policies {
+        let root = tree.add_line(format!("Escilation Policy - {}", policy.policy_name));
+        if show_services {
+            add_services(&root, &policy.services);
+        }
+        let oncalls = root.add_line("Oncalls".into());
+        for group in policy.oncall_groups {
+            if !filter(&group) {
+                continue;
+            }
+
+            let level = oncalls.add_line(format!("Level {}", group.depth));
+
+            let mut shifts: BTreeMap<(Option<String>, Option<String>), Vec<String>> =
+                BTreeMap::new();
+            for user in group.users {
+                let display = match &user.schedule {
+                    Some(schedule) => format!("{} via {}", user.to_display(), schedule),
+                    None => user.to_display(),
+                };
+                shifts
+                    .entry((user.shift_start, user.shift_end))
+                    .or_default()
+                    .push(display);
+            }
+
+            for ((start, end), users) in shifts {
+                level.add_line(format!(
+                    "{} to {} - {}",
+                    start.unwrap_or_else(|| "always".into()),
+                    end.unwrap_or_else(|| "always".into()),
+                    users.join(", ")
+                ));
+            }
+        }
+    }
+
+    tree.render()
+}
+
+fn add_services(root: &tree::OutputLine, services: &[String]) {
+    let node = root.add_line("Services".into());
+    for service in services {
+        node.add_line(service.clone());
+    }
+}
+
 pub fn write_file(path: &str, contents: &str) -> std::io::Result<()> {
     if path == "-" {
         println!("{}", contents);
@@ -0,0 +1,145 @@
+use chrono::Utc;
+use json::object;
+use std::collections::BTreeMap;
+
+use crate::v2::{ContactMethod, EscalationPolicy, PagerDutyUser, Service};
+
+use super::tree;
+
+pub fn build_json_output(
+    chains: &[(Service, EscalationPolicy)],
+    contact_methods: &BTreeMap<String, Vec<ContactMethod>>,
+) -> String {
+    let mut outputs = Vec::new();
+
+    for (service, policy) in chains {
+        let mut levels = Vec::new();
+        for (depth, users) in levels_of(policy) {
+            let mut user_outputs = Vec::new();
+            for user in users {
+                let mut methods = Vec::new();
+                for method in methods_of(contact_methods, user) {
+                    methods.push(object! {
+                        type: method.kind.clone(),
+                        label: method.label.clone(),
+                        address: method.address.clone()
+                    });
+                }
+
+                user_outputs.push(object! {
+                    id: user.id.clone(),
+                    name: user.name.clone(),
+                    email: user.email.clone(),
+                    shiftEnd: user.shift_end.clone(),
+                    schedule: user.schedule.clone(),
+                    contactMethods: methods
+                });
+            }
+
+            levels.push(object! {
+                level: depth,
+                escalationDelayMinutes: escalation_delay(policy, depth),
+                users: user_outputs
+            });
+        }
+
+        outputs.push(object! {
+            service: object! {
+                id: service.id.clone(),
+                name: service.name.clone(),
+                status: service.status.clone()
+            },
+            escalationPolicy: object! {
+                id: policy.id.clone(),
+                name: policy.policy_name.clone()
+            },
+            levels: levels
+        });
+    }
+
+    json::stringify_pretty(outputs, 2)
+}
+
+pub fn build_tree_output(
+    chains: &[(Service, EscalationPolicy)],
+    contact_methods: &BTreeMap<String, Vec<ContactMethod>>,
+) -> String {
+    let tree = tree::TreePrinter::default();
+    let now = Utc::now();
+
+    for (service, policy) in chains {
+        let root = tree.add_line(format!(
+            "Service - {} ({}) - {}",
+            service.name, service.id, service.status
+        ));
+        let chain = root.add_line(format!(
+            "Escalation Policy - {} ({})",
+            policy.policy_name, policy.id
+        ));
+
+        for (depth, users) in levels_of(policy) {
+            let level = match escalation_delay(policy, depth) {
+                Some(minutes) => {
+                    chain.add_line(format!("Level {}, escalates after {}m", depth, minutes))
+                }
+                None => chain.add_line(format!("Level {}", depth)),
+            };
+
+            if users.is_empty() {
+                level.add_line("Nobody on call".into());
+            }
+            for user in users {
+                let line = level.add_line(format!(
+                    "{} [{}]",
+                    user.to_display(),
+                    user.shift_display(now)
+                ));
+                for method in methods_of(contact_methods, user) {
+                    line.add_line(method.to_display());
+                }
+            }
+        }
+    }
+
+    tree.render()
+}
+
+/// Every level of the policy in order, including the ones nobody is on call for.
+fn levels_of(policy: &EscalationPolicy) -> Vec<(u8, Vec<&PagerDutyUser>)> {
+    let deepest = policy
+        .oncall_groups
+        .iter()
+        .map(|group| group.depth as usize)
+        .max()
+        .unwrap_or(0)
+        .max(policy.rules.len());
+
+    (1..=deepest)
+        .map(|depth| {
+            let users = policy
+                .oncall_groups
+                .iter()
+                .filter(|group| group.depth as usize == depth)
+                .flat_map(|group| group.users.iter())
+                .collect();
+            (depth as u8, users)
+        })
+        .collect()
+}
+
+fn escalation_delay(policy: &EscalationPolicy, depth: u8) -> Option<u32> {
+    policy
+        .rules
+        .get(depth as usize - 1)
+        .map(|rule| rule.escalation_delay_in_minutes)
+}
+
+fn methods_of<'a>(
+    contact_methods: &'a BTreeMap<String, Vec<ContactMethod>>,
+    user: &PagerDutyUser,
+) -> &'a [ContactMethod] {
+    contact_methods
+        .get(&user.id)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
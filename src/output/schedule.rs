@@ -0,0 +1,224 @@
+use chrono::Duration;
+use json::{object, JsonValue};
+
+use crate::duration::format_duration;
+use crate::v2::{Schedule, ScheduleEntry, ScheduleOverride};
+
+use super::tree;
+
+fn entries_to_json(entries: &[ScheduleEntry]) -> Vec<JsonValue> {
+    entries
+        .iter()
+        .map(|entry| {
+            object! {
+                start: entry.start.clone(),
+                end: entry.end.clone(),
+                userId: entry.user_id.clone(),
+                userName: entry.user_name.clone()
+            }
+        })
+        .collect()
+}
+
+pub fn build_csv_output(schedules: Vec<Schedule>) -> String {
+    let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
+
+    wtr.write_record([
+        "Schedule ID",
+        "Schedule",
+        "Layer",
+        "start",
+        "end",
+        "user id",
+        "name",
+    ])
+    .expect("To be able to write header");
+
+    for schedule in schedules {
+        let layers = schedule
+            .layers
+            .iter()
+            .map(|layer| (layer.name.as_str(), &layer.entries))
+            .chain(std::iter::once((
+                "Final Schedule",
+                &schedule.final_schedule,
+            )));
+
+        for (layer_name, entries) in layers {
+            for entry in entries {
+                wtr.write_record([
+                    &schedule.id,
+                    &schedule.name,
+                    layer_name,
+                    &entry.start,
+                    &entry.end,
+                    &entry.user_id,
+                    &entry.user_name,
+                ])
+                .expect("to be able to write row");
+            }
+        }
+    }
+
+    String::from_utf8(wtr.into_inner().expect("to be able to get vec"))
+        .expect("To be able to serialize CSV")
+}
+
+pub fn build_json_output(schedules: Vec<Schedule>) -> String {
+    let mut outputs = Vec::new();
+
+    for schedule in schedules {
+        let layers: Vec<JsonValue> = schedule
+            .layers
+            .iter()
+            .map(|layer| {
+                object! {
+                    id: layer.id.clone(),
+                    name: layer.name.clone(),
+                    start: layer.start.clone(),
+                    end: layer.end.clone(),
+                    rotationTurnLengthSeconds: layer.rotation_turn_length_seconds,
+                    users: layer.users.clone(),
+                    entries: entries_to_json(&layer.entries)
+                }
+            })
+            .collect();
+
+        outputs.push(object! {
+            id: schedule.id,
+            name: schedule.name,
+            timeZone: schedule.time_zone,
+            description: schedule.description,
+            url: schedule.html_url,
+            layers: layers,
+            finalSchedule: entries_to_json(&schedule.final_schedule)
+        });
+    }
+
+    json::stringify_pretty(outputs, 2)
+}
+
+pub fn build_tree_output(schedules: Vec<Schedule>) -> String {
+    let tree = tree::TreePrinter::default();
+
+    for schedule in schedules {
+        let root = tree.add_line(format!(
+            "Schedule - {} ({})",
+            schedule.name, schedule.time_zone
+        ));
+
+        let layers = root.add_line("Layers".into());
+        for layer in schedule.layers {
+            let layer_line = layers.add_line(format!(
+                "{} - rotates every {} - {}",
+                layer.name,
+                format_duration(Duration::seconds(layer.rotation_turn_length_seconds)),
+                layer.users.join(", ")
+            ));
+            for entry in layer.entries {
+                layer_line.add_line(format_entry(&entry));
+            }
+        }
+
+        let final_schedule = root.add_line("Final Schedule".into());
+        for entry in schedule.final_schedule {
+            final_schedule.add_line(format_entry(&entry));
+        }
+    }
+
+    tree.render()
+}
+
+pub fn build_overrides_csv_output(schedule: &Schedule, overrides: Vec<ScheduleOverride>) -> String {
+    let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
+
+    wtr.write_record([
+        "Schedule ID",
+        "Schedule",
+        "Override ID",
+        "start",
+        "end",
+        "user id",
+        "name",
+    ])
+    .expect("To be able to write header");
+
+    for item in overrides {
+        wtr.write_record([
+            &schedule.id,
+            &schedule.name,
+            &item.id,
+            &item.start,
+            &item.end,
+            &item.user_id,
+            &item.user_name,
+        ])
+        .expect("to be able to write row");
+    }
+
+    String::from_utf8(wtr.into_inner().expect("to be able to get vec"))
+        .expect("To be able to serialize CSV")
+}
+
+pub fn build_overrides_json_output(
+    schedule: &Schedule,
+    overrides: Vec<ScheduleOverride>,
+) -> String {
+    let outputs: Vec<JsonValue> = overrides
+        .into_iter()
+        .map(|item| {
+            object! {
+                scheduleId: schedule.id.clone(),
+                schedule: schedule.name.clone(),
+                id: item.id,
+                start: item.start,
+                end: item.end,
+                userId: item.user_id,
+                userName: item.user_name
+            }
+        })
+        .collect();
+
+    json::stringify_pretty(outputs, 2)
+}
+
+pub fn build_overrides_tree_output(
+    schedule: &Schedule,
+    overrides: Vec<ScheduleOverride>,
+) -> String {
+    let tree = tree::TreePrinter::default();
+
+    let root = tree.add_line(format!(
+        "Schedule - {} ({})",
+        schedule.name, schedule.time_zone
+    ));
+    let overrides_line = root.add_line("Overrides".into());
+    for item in overrides {
+        overrides_line.add_line(format!(
+            "{} - {} to {} - {}",
+            item.id, item.start, item.end, item.user_name
+        ));
+    }
+
+    tree.render()
+}
+
+/// Show what the final schedule will look like, without changing anything.
+pub fn build_preview_output(schedule: &Schedule, entries: &[ScheduleEntry]) -> String {
+    let tree = tree::TreePrinter::default();
+
+    let root = tree.add_line(format!(
+        "Schedule - {} ({})",
+        schedule.name, schedule.time_zone
+    ));
+    let final_schedule = root.add_line("Final Schedule (preview)".into());
+    for entry in entries {
+        final_schedule.add_line(format_entry(entry));
+    }
+
+    tree.render()
+}
+
+fn format_entry(entry: &ScheduleEntry) -> String {
+    format!("{} to {} - {}", entry.start, entry.end, entry.user_name)
+}
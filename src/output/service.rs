@@ -0,0 +1,223 @@
+use chrono::Duration;
+use json::object;
+
+use crate::duration::format_duration;
+use crate::v2::{Integration, Service, UrgencyRule};
+
+use super::tree;
+
+pub fn build_csv_output(services: Vec<Service>) -> String {
+    let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
+
+    wtr.write_record([
+        "Service ID",
+        "Service",
+        "Status",
+        "Escalation Policy",
+        "Urgency",
+        "Acknowledgement Timeout",
+        "Auto Resolve Timeout",
+        "Alert Grouping",
+        "Integrations",
+    ])
+    .expect("To be able to write header");
+
+    for service in services {
+        let integrations: Vec<String> = service
+            .integrations
+            .iter()
+            .map(integration_display)
+            .collect();
+
+        wtr.write_record([
+            &service.id,
+            &service.name,
+            &service.status,
+            &service.escalation_policy_name,
+            &service.urgency.to_display(),
+            &seconds(service.acknowledgement_timeout),
+            &seconds(service.auto_resolve_timeout),
+            &service
+                .alert_grouping
+                .map(|grouping| grouping.to_display())
+                .unwrap_or_default(),
+            &integrations.join(", "),
+        ])
+        .expect("to be able to write row");
+    }
+
+    String::from_utf8(wtr.into_inner().expect("to be able to get vec"))
+        .expect("To be able to serialize CSV")
+}
+
+pub fn build_json_output(services: Vec<Service>) -> String {
+    let mut outputs = Vec::new();
+
+    for service in services {
+        let urgency = match service.urgency {
+            UrgencyRule::Constant(urgency) => object! {
+                type: "constant",
+                urgency: urgency
+            },
+            UrgencyRule::SupportHours { during, outside } => object! {
+                type: "use_support_hours",
+                duringSupportHours: during,
+                outsideSupportHours: outside
+            },
+        };
+
+        let alert_grouping = service.alert_grouping.map(|grouping| {
+            object! {
+                type: grouping.kind,
+                timeoutMinutes: grouping.timeout
+            }
+        });
+
+        let mut integrations = Vec::new();
+        for integration in service.integrations {
+            integrations.push(object! {
+                id: integration.id,
+                name: integration.name,
+                vendor: integration.vendor,
+                key: integration.key
+            });
+        }
+
+        outputs.push(object! {
+            id: service.id,
+            name: service.name,
+            description: service.description,
+            status: service.status,
+            escalationPolicy: object! {
+                id: service.escalation_policy_id,
+                name: service.escalation_policy_name
+            },
+            urgency: urgency,
+            acknowledgementTimeoutSeconds: service.acknowledgement_timeout,
+            autoResolveTimeoutSeconds: service.auto_resolve_timeout,
+            alertGrouping: alert_grouping,
+            integrations: integrations,
+            teams: service.teams
+        });
+    }
+
+    json::stringify_pretty(outputs, 2)
+}
+
+pub fn build_tree_output(services: Vec<Service>) -> String {
+    let tree = tree::TreePrinter::default();
+
+    for service in services {
+        let root = tree.add_line(format!("Service - {} ({})", service.name, service.id));
+        if let Some(description) = service.description {
+            root.add_line(description);
+        }
+
+        root.add_line(format!("Status - {}", service.status));
+        root.add_line(format!(
+            "Escalation Policy - {} ({})",
+            service.escalation_policy_name, service.escalation_policy_id
+        ));
+        root.add_line(format!("Urgency - {}", service.urgency.to_display()));
+        root.add_line(format!(
+            "Acknowledgement Timeout - {}",
+            timeout(service.acknowledgement_timeout)
+        ));
+        root.add_line(format!(
+            "Auto Resolve Timeout - {}",
+            timeout(service.auto_resolve_timeout)
+        ));
+        root.add_line(format!(
+            "Alert Grouping - {}",
+            service
+                .alert_grouping
+                .map(|grouping| grouping.to_display())
+                .unwrap_or_else(|| "off".into())
+        ));
+
+        let integrations = root.add_line("Integrations".into());
+        for integration in &service.integrations {
+            integrations.add_line(integration_display(integration));
+        }
+    }
+
+    tree.render()
+}
+
+/// `Datadog (Datadog) - <key>`, with the vendor and key only when PagerDuty returned them.
+fn integration_display(integration: &Integration) -> String {
+    let mut display = integration.name.clone();
+    if let Some(vendor) = &integration.vendor {
+        display.push_str(&format!(" ({})", vendor));
+    }
+    if let Some(key) = &integration.key {
+        display.push_str(&format!(" - {}", key));
+    }
+    display
+}
+
+fn timeout(seconds: Option<u64>) -> String {
+    match seconds {
+        Some(seconds) => format_duration(Duration::seconds(seconds as i64)),
+        None => "disabled".into(),
+    }
+}
+
+fn seconds(seconds: Option<u64>) -> String {
+    seconds.map(|s| s.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v2::AlertGrouping;
+
+    #[test]
+    fn csv_has_a_row_per_service() {
+        let services = vec![Service {
+            id: "PX01HJD".into(),
+            name: "checkout-api".into(),
+            description: None,
+            escalation_policy_id: "P9OFD2O".into(),
+            acknowledgement_timeout: Some(1800),
+            auto_resolve_timeout: None,
+            alert_creation: Some("create_alerts_and_incidents".into()),
+            teams: vec![],
+            status: "warning".into(),
+            escalation_policy_name: "Payments".into(),
+            urgency: UrgencyRule::SupportHours {
+                during: "high".into(),
+                outside: "low".into(),
+            },
+            alert_grouping: Some(AlertGrouping {
+                kind: "time".into(),
+                timeout: Some(5),
+            }),
+            integrations: vec![
+                Integration {
+                    id: "PVAES35".into(),
+                    name: "Datadog".into(),
+                    vendor: Some("Datadog".into()),
+                    key: Some("8d6a0c1b".into()),
+                },
+                Integration {
+                    id: "PAJ0Y4V".into(),
+                    name: "Events API v1".into(),
+                    vendor: None,
+                    key: None,
+                },
+            ],
+        }];
+
+        let output = build_csv_output(services);
+        let rows: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            vec![
+                "Service ID,Service,Status,Escalation Policy,Urgency,Acknowledgement Timeout,Auto Resolve Timeout,Alert Grouping,Integrations",
+                "PX01HJD,checkout-api,warning,Payments,\"high during support hours, low outside\",1800,,\"time, 5m\",\"Datadog (Datadog) - 8d6a0c1b, Events API v1\"",
+            ],
+            rows
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use json::object;
+
+use crate::v2::TeamDetails;
+
+use super::tree;
+
+/// One row per member, escalation policy and service, so a team that owns nothing has no rows.
+pub fn build_csv_output(teams: Vec<TeamDetails>) -> String {
+    let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
+
+    wtr.write_record(["Team ID", "Team", "Type", "Name", "Role"])
+        .expect("To be able to write header");
+
+    for details in teams {
+        let team = &details.team;
+        for member in &details.members {
+            wtr.write_record([&team.id, &team.name, "member", &member.name, &member.role])
+                .expect("to be able to write row");
+        }
+        for policy in &details.escalation_policies {
+            wtr.write_record([&team.id, &team.name, "escalation_policy", policy, ""])
+                .expect("to be able to write row");
+        }
+        for service in &details.services {
+            wtr.write_record([&team.id, &team.name, "service", service, ""])
+                .expect("to be able to write row");
+        }
+    }
+
+    String::from_utf8(wtr.into_inner().expect("to be able to get vec"))
+        .expect("To be able to serialize CSV")
+}
+
+pub fn build_json_output(teams: Vec<TeamDetails>) -> String {
+    let mut outputs = Vec::new();
+
+    for details in teams {
+        let mut members = Vec::new();
+        for member in details.members {
+            members.push(object! {
+                id: member.id,
+                name: member.name,
+                role: member.role
+            });
+        }
+
+        outputs.push(object! {
+            id: details.team.id,
+            name: details.team.name,
+            description: details.team.description,
+            members: members,
+            escalationPolicies: details.escalation_policies,
+            services: details.services
+        });
+    }
+
+    json::stringify_pretty(outputs, 2)
+}
+
+pub fn build_tree_output(teams: Vec<TeamDetails>) -> String {
+    let tree = tree::TreePrinter::default();
+
+    for details in teams {
+        let root = tree.add_line(format!(
+            "Team - {} ({})",
+            details.team.name, details.team.id
+        ));
+        if let Some(description) = details.team.description {
+            root.add_line(description);
+        }
+
+        let members = root.add_line("Members".into());
+        for member in details.members {
+            members.add_line(format!("{} - {}", member.name, member.role));
+        }
+        let policies = root.add_line("Escalation Policies".into());
+        for policy in details.escalation_policies {
+            policies.add_line(policy);
+        }
+        let services = root.add_line("Services".into());
+        for service in details.services {
+            services.add_line(service);
+        }
+    }
+
+    tree.render()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v2::{Team, TeamMember};
+
+    #[test]
+    fn csv_has_a_row_per_member_policy_and_service() {
+        let teams = vec![TeamDetails {
+            team: Team {
+                id: "PQ9K7I8".into(),
+                name: "Payments".into(),
+                description: None,
+            },
+            members: vec![TeamMember {
+                id: "PXPGF42".into(),
+                name: "Earline Greenholt".into(),
+                role: "manager".into(),
+            }],
+            escalation_policies: vec!["Default".into()],
+            services: vec![],
+        }];
+
+        let output = build_csv_output(teams);
+        let rows: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            vec![
+                "Team ID,Team,Type,Name,Role",
+                "PQ9K7I8,Payments,member,Earline Greenholt,manager",
+                "PQ9K7I8,Payments,escalation_policy,Default,",
+            ],
+            rows
+        );
+    }
+}
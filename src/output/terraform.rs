@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+
+use crate::v2::{Account, EscalationPolicy, Schedule, Service, Team, User};
+
+const TEAM: &str = "pagerduty_team";
+const USER: &str = "pagerduty_user";
+const SCHEDULE: &str = "pagerduty_schedule";
+const ESCALATION_POLICY: &str = "pagerduty_escalation_policy";
+const SERVICE: &str = "pagerduty_service";
+
+/// The right hand side of an attribute.
+enum Value {
+    Str(String),
+    Number(u64),
+    /// Written as-is, like a reference to another resource.
+    Expression(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn render(&self) -> String {
+        match self {
+            Value::Str(value) => format!("\"{}\"", escape(value)),
+            Value::Number(value) => value.to_string(),
+            Value::Expression(value) => value.clone(),
+            Value::List(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(Value::render)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+struct Block {
+    header: String,
+    attributes: Vec<(&'static str, Value)>,
+    blocks: Vec<Block>,
+}
+
+impl Block {
+    fn new(header: String) -> Self {
+        Block {
+            header,
+            attributes: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    fn resource(kind: &str, name: &str) -> Self {
+        Block::new(format!("resource \"{}\" \"{}\"", kind, name))
+    }
+
+    fn attribute(mut self, name: &'static str, value: Value) -> Self {
+        self.attributes.push((name, value));
+        self
+    }
+
+    fn optional(self, name: &'static str, value: Option<&String>) -> Self {
+        match value {
+            Some(value) => self.attribute(name, Value::Str(value.clone())),
+            None => self,
+        }
+    }
+
+    fn block(mut self, block: Block) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    /// Attributes are aligned on `=` the same way `terraform fmt` does.
+    fn render(&self, indent: usize, out: &mut String) {
+        let padding = "  ".repeat(indent);
+        out.push_str(&format!("{}{} {{\n", padding, self.header));
+
+        let width = self
+            .attributes
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        for (name, value) in &self.attributes {
+            out.push_str(&format!(
+                "{}  {:width$} = {}\n",
+                padding,
+                name,
+                value.render(),
+                width = width
+            ));
+        }
+
+        for block in &self.blocks {
+            if !self.attributes.is_empty() || !std::ptr::eq(block, &self.blocks[0]) {
+                out.push('\n');
+            }
+            block.render(indent + 1, out);
+        }
+
+        out.push_str(&format!("{}}}\n", padding));
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace("${", "$${")
+        .replace("%{", "%%{")
+}
+
+/// Turn a PagerDuty name into a Terraform identifier, like `Payments Primary` to `payments_primary`.
+fn identifier(name: &str) -> String {
+    let mut identifier = String::new();
+    for c in name.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            identifier.push(c);
+        } else if !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+    }
+
+    let identifier = identifier.trim_matches('_').to_string();
+    match identifier.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => identifier,
+        _ => format!("_{}", identifier),
+    }
+}
+
+/// Resource names for every exported object, keyed by kind and PagerDuty ID.
+#[derive(Default)]
+struct ResourceNames {
+    names: BTreeMap<(&'static str, String), String>,
+}
+
+impl ResourceNames {
+    /// Names that would collide get the PagerDuty ID appended, so the result doesn't
+    /// depend on the order PagerDuty returned them in.
+    fn assign<'a, I>(&mut self, kind: &'static str, objects: I)
+    where
+        I: Iterator<Item = (&'a String, &'a String)> + Clone,
+    {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for (_, name) in objects.clone() {
+            *counts.entry(identifier(name)).or_default() += 1;
+        }
+
+        for (id, name) in objects {
+            let identifier = identifier(name);
+            let identifier = if counts[&identifier] > 1 {
+                format!("{}_{}", identifier, id.to_lowercase())
+            } else {
+                identifier
+            };
+            self.names.insert((kind, id.clone()), identifier);
+        }
+    }
+
+    fn name(&self, kind: &'static str, id: &str) -> &str {
+        &self.names[&(kind, id.to_string())]
+    }
+
+    /// `kind.name.id` when the object is part of the export, otherwise the raw ID.
+    fn reference(&self, kind: &'static str, id: &str) -> Value {
+        match self.names.get(&(kind, id.to_string())) {
+            Some(name) => Value::Expression(format!("{}.{}.id", kind, name)),
+            None => Value::Str(id.to_string()),
+        }
+    }
+
+    fn references(&self, kind: &'static str, ids: &[String]) -> Value {
+        Value::List(ids.iter().map(|id| self.reference(kind, id)).collect())
+    }
+
+    fn import(&self, kind: &'static str, id: &str) -> Block {
+        Block::new("import".into())
+            .attribute(
+                "to",
+                Value::Expression(format!("{}.{}", kind, self.name(kind, id))),
+            )
+            .attribute("id", Value::Str(id.to_string()))
+    }
+}
+
+/// Render the account as Terraform resources, each followed by an `import` block so an
+/// existing account can be brought under Terraform with a single `terraform apply`.
+pub fn build_terraform_output(account: &Account) -> String {
+    let mut names = ResourceNames::default();
+    names.assign(TEAM, account.teams.iter().map(|t| (&t.id, &t.name)));
+    names.assign(USER, account.users.iter().map(|u| (&u.id, &u.name)));
+    names.assign(SCHEDULE, account.schedules.iter().map(|s| (&s.id, &s.name)));
+    names.assign(
+        ESCALATION_POLICY,
+        account
+            .escalation_policies
+            .iter()
+            .map(|p| (&p.id, &p.policy_name)),
+    );
+    names.assign(SERVICE, account.services.iter().map(|s| (&s.id, &s.name)));
+
+    let mut blocks = Vec::new();
+    for team in &account.teams {
+        blocks.push(team_resource(&names, team));
+        blocks.push(names.import(TEAM, &team.id));
+    }
+    for user in &account.users {
+        blocks.push(user_resource(&names, user));
+        blocks.push(names.import(USER, &user.id));
+    }
+    for schedule in &account.schedules {
+        blocks.push(schedule_resource(&names, schedule));
+        blocks.push(names.import(SCHEDULE, &schedule.id));
+    }
+    for policy in &account.escalation_policies {
+        blocks.push(escalation_policy_resource(&names, policy));
+        blocks.push(names.import(ESCALATION_POLICY, &policy.id));
+    }
+    for service in &account.services {
+        blocks.push(service_resource(&names, service));
+        blocks.push(names.import(SERVICE, &service.id));
+    }
+
+    let mut out = String::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        block.render(0, &mut out);
+    }
+    out
+}
+
+fn team_resource(names: &ResourceNames, team: &Team) -> Block {
+    Block::resource(TEAM, names.name(TEAM, &team.id))
+        .attribute("name", Value::Str(team.name.clone()))
+        .optional("description", team.description.as_ref())
+}
+
+fn user_resource(names: &ResourceNames, user: &User) -> Block {
+    // The account owner's role can't be assigned, only transferred in the web UI.
+    let role = user.role.as_ref().filter(|role| role.as_str() != "owner");
+
+    Block::resource(USER, names.name(USER, &user.id))
+        .attribute("name", Value::Str(user.name.clone()))
+        .attribute("email", Value::Str(user.email.clone()))
+        .optional("role", role)
+        .optional("time_zone", user.time_zone.as_ref())
+        .optional("job_title", user.job_title.as_ref())
+        .optional("description", user.description.as_ref())
+}
+
+fn schedule_resource(names: &ResourceNames, schedule: &Schedule) -> Block {
+    let mut block = Block::resource(SCHEDULE, names.name(SCHEDULE, &schedule.id))
+        .attribute("name", Value::Str(schedule.name.clone()))
+        .attribute("time_zone", Value::Str(schedule.time_zone.clone()))
+        .optional("description", schedule.description.as_ref());
+    if !schedule.teams.is_empty() {
+        block = block.attribute("teams", names.references(TEAM, &schedule.teams));
+    }
+
+    for layer in &schedule.layers {
+        let mut layer_block = Block::new("layer".into())
+            .attribute("name", Value::Str(layer.name.clone()))
+            .attribute("start", Value::Str(layer.start.clone()))
+            .optional("end", layer.end.as_ref())
+            .attribute(
+                "rotation_virtual_start",
+                Value::Str(
+                    layer
+                        .rotation_virtual_start
+                        .clone()
+                        .unwrap_or_else(|| layer.start.clone()),
+                ),
+            )
+            .attribute(
+                "rotation_turn_length_seconds",
+                Value::Number(layer.rotation_turn_length_seconds.max(0) as u64),
+            )
+            .attribute("users", names.references(USER, &layer.user_ids));
+
+        for restriction in &layer.restrictions {
+            let mut restriction_block = Block::new("restriction".into())
+                .attribute("type", Value::Str(restriction.restriction_type.clone()))
+                .attribute(
+                    "start_time_of_day",
+                    Value::Str(restriction.start_time_of_day.clone()),
+                )
+                .attribute(
+                    "duration_seconds",
+                    Value::Number(restriction.duration_seconds),
+                );
+            if let Some(day) = restriction.start_day_of_week {
+                restriction_block =
+                    restriction_block.attribute("start_day_of_week", Value::Number(day.into()));
+            }
+            layer_block = layer_block.block(restriction_block);
+        }
+
+        block = block.block(layer_block);
+    }
+
+    block
+}
+
+fn escalation_policy_resource(names: &ResourceNames, policy: &EscalationPolicy) -> Block {
+    let mut block = Block::resource(ESCALATION_POLICY, names.name(ESCALATION_POLICY, &policy.id))
+        .attribute("name", Value::Str(policy.policy_name.clone()))
+        .optional("description", policy.description.as_ref())
+        .attribute("num_loops", Value::Number(policy.num_loops.into()));
+    if !policy.teams.is_empty() {
+        block = block.attribute("teams", names.references(TEAM, &policy.teams));
+    }
+
+    for rule in &policy.rules {
+        let mut rule_block = Block::new("rule".into()).attribute(
+            "escalation_delay_in_minutes",
+            Value::Number(rule.escalation_delay_in_minutes.into()),
+        );
+        for target in &rule.targets {
+            let id = match target.target_type.as_str() {
+                "schedule_reference" => names.reference(SCHEDULE, &target.id),
+                _ => names.reference(USER, &target.id),
+            };
+            rule_block = rule_block.block(
+                Block::new("target".into())
+                    .attribute("type", Value::Str(target.target_type.clone()))
+                    .attribute("id", id),
+            );
+        }
+        block = block.block(rule_block);
+    }
+
+    block
+}
+
+fn service_resource(names: &ResourceNames, service: &Service) -> Block {
+    // Terraform takes the timeouts as strings, with "null" to turn them off.
+    let timeout = |seconds: Option<u64>| {
+        Value::Str(seconds.map_or_else(|| "null".to_string(), |seconds| seconds.to_string()))
+    };
+
+    Block::resource(SERVICE, names.name(SERVICE, &service.id))
+        .attribute("name", Value::Str(service.name.clone()))
+        .optional("description", service.description.as_ref())
+        .attribute(
+            "escalation_policy",
+            names.reference(ESCALATION_POLICY, &service.escalation_policy_id),
+        )
+        .attribute(
+            "acknowledgement_timeout",
+            timeout(service.acknowledgement_timeout),
+        )
+        .attribute(
+            "auto_resolve_timeout",
+            timeout(service.auto_resolve_timeout),
+        )
+        .optional("alert_creation", service.alert_creation.as_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v2::{EscalationRule, EscalationTarget};
+
+    #[test]
+    fn identifiers_are_valid_terraform_names() {
+        assert_eq!("payments_primary", identifier("Payments Primary"));
+        assert_eq!("o_connell", identifier("O'Connell"));
+        assert_eq!("_24_7_support", identifier("24/7 Support"));
+    }
+
+    #[test]
+    fn duplicate_names_get_the_id_appended() {
+        let first = ("PABC123".to_string(), "Payments".to_string());
+        let second = ("PDEF456".to_string(), "payments".to_string());
+
+        let mut names = ResourceNames::default();
+        names.assign(
+            TEAM,
+            vec![(&first.0, &first.1), (&second.0, &second.1)].into_iter(),
+        );
+
+        assert_eq!("payments_pabc123", names.name(TEAM, "PABC123"));
+        assert_eq!("payments_pdef456", names.name(TEAM, "PDEF456"));
+    }
+
+    #[test]
+    fn references_resolve_to_resources() {
+        let policy = EscalationPolicy {
+            id: "P9OFD2O".into(),
+            description: None,
+            policy_name: "Default".into(),
+            oncall_groups: vec![],
+            services: vec![],
+            num_loops: 0,
+            teams: vec!["PQ9K7I8".into()],
+            rules: vec![EscalationRule {
+                escalation_delay_in_minutes: 30,
+                targets: vec![
+                    EscalationTarget {
+                        id: "PPC00ZX".into(),
+                        target_type: "user_reference".into(),
+                    },
+                    EscalationTarget {
+                        id: "PMISSING".into(),
+                        target_type: "schedule_reference".into(),
+                    },
+                ],
+            }],
+        };
+
+        let account = Account {
+            escalation_policies: vec![policy],
+            teams: vec![Team {
+                id: "PQ9K7I8".into(),
+                name: "Payments".into(),
+                description: Some("Takes \"money\"".into()),
+            }],
+            users: vec![User {
+                id: "PPC00ZX".into(),
+                name: "John Doe".into(),
+                email: "john@example.com".into(),
+                role: Some("owner".into()),
+                time_zone: None,
+                job_title: None,
+                description: None,
+                teams: vec![],
+                contact_methods: vec![],
+                notification_rules: vec![],
+            }],
+            ..Default::default()
+        };
+
+        let output = build_terraform_output(&account);
+
+        assert!(output.contains("  description = \"Takes \\\"money\\\"\"\n"));
+        assert!(!output.contains("owner"));
+        assert!(output.contains("  teams     = [pagerduty_team.payments.id]\n"));
+        assert!(output.contains("      id   = pagerduty_user.john_doe.id\n"));
+        assert!(output.contains("      id   = \"PMISSING\"\n"));
+        assert!(output.contains(
+            "import {\n  to = pagerduty_escalation_policy.default\n  id = \"P9OFD2O\"\n}\n"
+        ));
+    }
+}
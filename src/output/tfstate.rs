@@ -2,12 +2,32 @@ use std::collections::BTreeMap;
 
 use serde::Serialize;
 
-use crate::v2::EscalationPolicy;
+use crate::v2::{EscalationPolicy, PagerDutyError};
+
+/// What to do when more than one escalation policy has the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupeStrategy {
+    /// Leave the policies out of `escalation_policies`, they're only under `duplicates`.
+    Omit,
+    /// Export every policy, adding the ID to the names that clash, like `Default (P9OFD2O)`.
+    SuffixId,
+    /// Only export the policy with the lowest ID, so the result doesn't change between runs.
+    First,
+    /// Fail the export.
+    Error,
+}
 
 #[derive(Debug, Serialize)]
 pub struct TfStateExportData {
     escalation_policies: BTreeMap<String, String>,
-    duplicates: Vec<String>,
+    duplicates: BTreeMap<String, Vec<DuplicatePolicy>>,
+}
+
+/// One of the policies sharing a name, with enough detail to tell them apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicatePolicy {
+    id: String,
+    services: Vec<String>,
 }
 
 impl std::default::Default for TfStateExportData {
@@ -20,20 +40,136 @@ impl std::default::Default for TfStateExportData {
 }
 
 impl TfStateExportData {
-    pub fn add_escalation_policy(&mut self, policy: EscalationPolicy) {
-        if let Some(value) = self.escalation_policies.get(&policy.policy_name) {
-            if value != &policy.id {
-                eprintln!(
-                    "Warning! Duplicate policy with name {} found!",
-                    policy.policy_name
-                );
-                self.escalation_policies.remove(&policy.policy_name);
-                self.duplicates.push(policy.policy_name);
-                return;
+    pub fn from_policies(
+        policies: Vec<EscalationPolicy>,
+        strategy: DedupeStrategy,
+    ) -> Result<Self, PagerDutyError> {
+        let mut by_name: BTreeMap<String, BTreeMap<String, EscalationPolicy>> = BTreeMap::new();
+        for policy in policies {
+            by_name
+                .entry(policy.policy_name.clone())
+                .or_default()
+                .insert(policy.id.clone(), policy);
+        }
+
+        let mut tf_state = TfStateExportData::default();
+        for (name, policies) in by_name {
+            if policies.len() == 1 {
+                let id = policies.into_keys().next().unwrap();
+                tf_state.escalation_policies.insert(name, id);
+                continue;
+            }
+
+            eprintln!("Warning! Duplicate policy with name {} found!", name);
+
+            // Ordered by ID, as PagerDuty doesn't guarantee the order of policies sharing a name.
+            let duplicates: Vec<DuplicatePolicy> = policies
+                .into_values()
+                .map(|policy| DuplicatePolicy {
+                    id: policy.id,
+                    services: policy.services,
+                })
+                .collect();
+
+            match strategy {
+                DedupeStrategy::SuffixId => {
+                    for duplicate in &duplicates {
+                        tf_state
+                            .escalation_policies
+                            .insert(format!("{} ({})", name, duplicate.id), duplicate.id.clone());
+                    }
+                }
+                DedupeStrategy::First => {
+                    tf_state
+                        .escalation_policies
+                        .insert(name.clone(), duplicates[0].id.clone());
+                }
+                DedupeStrategy::Omit | DedupeStrategy::Error => {}
             }
+
+            tf_state.duplicates.insert(name, duplicates);
+        }
+
+        if strategy == DedupeStrategy::Error && !tf_state.duplicates.is_empty() {
+            return Err(PagerDutyError::Duplicates {
+                kind: "escalation policy",
+                names: tf_state.duplicates.into_keys().collect(),
+            });
+        }
+
+        Ok(tf_state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn policies() -> Vec<EscalationPolicy> {
+        let mut second = EscalationPolicy::new("PB", "Payments");
+        second.services = vec!["payments-worker".into()];
+        let mut first = EscalationPolicy::new("PA", "Payments");
+        first.services = vec!["payments-api".into()];
+
+        vec![
+            EscalationPolicy::new("P9OFD2O", "Default"),
+            second,
+            first,
+            // Seen again on a later page, which isn't a duplicate.
+            EscalationPolicy::new("P9OFD2O", "Default"),
+        ]
+    }
+
+    #[test]
+    fn omit_leaves_clashing_names_out() {
+        let tf_state = TfStateExportData::from_policies(policies(), DedupeStrategy::Omit).unwrap();
+
+        assert_eq!(1, tf_state.escalation_policies.len());
+        assert_eq!("P9OFD2O", tf_state.escalation_policies["Default"]);
+        assert_eq!(2, tf_state.duplicates["Payments"].len());
+    }
+
+    #[test]
+    fn suffix_id_keeps_every_policy() {
+        let tf_state =
+            TfStateExportData::from_policies(policies(), DedupeStrategy::SuffixId).unwrap();
+
+        assert_eq!(3, tf_state.escalation_policies.len());
+        assert_eq!("P9OFD2O", tf_state.escalation_policies["Default"]);
+        assert_eq!("PA", tf_state.escalation_policies["Payments (PA)"]);
+        assert_eq!("PB", tf_state.escalation_policies["Payments (PB)"]);
+        assert_eq!(
+            vec![
+                DuplicatePolicy {
+                    id: "PA".into(),
+                    services: vec!["payments-api".into()]
+                },
+                DuplicatePolicy {
+                    id: "PB".into(),
+                    services: vec!["payments-worker".into()]
+                },
+            ],
+            tf_state.duplicates["Payments"]
+        );
+    }
+
+    #[test]
+    fn first_keeps_the_lowest_id() {
+        let tf_state = TfStateExportData::from_policies(policies(), DedupeStrategy::First).unwrap();
+
+        assert_eq!(2, tf_state.escalation_policies.len());
+        assert_eq!("PA", tf_state.escalation_policies["Payments"]);
+        assert_eq!(2, tf_state.duplicates["Payments"].len());
+    }
+
+    #[test]
+    fn error_lists_the_duplicate_names() {
+        match TfStateExportData::from_policies(policies(), DedupeStrategy::Error) {
+            Err(PagerDutyError::Duplicates { names, .. }) => assert_eq!(vec!["Payments"], names),
+            other => panic!("Unexpected result {:?}", other),
         }
 
-        self.escalation_policies
-            .insert(policy.policy_name, policy.id);
+        let unique = vec![EscalationPolicy::new("P9OFD2O", "Default")];
+        assert!(TfStateExportData::from_policies(unique, DedupeStrategy::Error).is_ok());
     }
 }
@@ -0,0 +1,210 @@
+use json::object;
+
+use crate::v2::{ContactMethod, NotificationRule, User};
+
+use super::tree;
+
+/// Email is left out, every user has one and it's already in the user's row.
+const REACHABLE_BY: [&str; 3] = ["phone", "sms", "push"];
+
+pub fn build_csv_output(users: Vec<User>) -> String {
+    let mut wtr = csv::WriterBuilder::new().from_writer(vec![]);
+
+    wtr.write_record([
+        "User ID",
+        "Name",
+        "Email",
+        "Role",
+        "Time Zone",
+        "Job Title",
+        "Phone",
+        "SMS",
+        "Push",
+        "High Urgency Rules",
+        "Low Urgency Rules",
+        "Problems",
+    ])
+    .expect("To be able to write header");
+
+    for user in users {
+        let methods = |kind: &str| -> String {
+            user.contact_methods
+                .iter()
+                .filter(|method| method.kind == kind)
+                .map(method_display)
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let rules = |urgency: &str| -> String {
+            rules_for(&user.notification_rules, urgency)
+                .map(NotificationRule::to_display)
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        wtr.write_record([
+            &user.id,
+            &user.name,
+            &user.email,
+            user.role.as_deref().unwrap_or_default(),
+            user.time_zone.as_deref().unwrap_or_default(),
+            user.job_title.as_deref().unwrap_or_default(),
+            &methods("phone"),
+            &methods("sms"),
+            &methods("push"),
+            &rules("high"),
+            &rules("low"),
+            &user.readiness_problems().join(", "),
+        ])
+        .expect("to be able to write row");
+    }
+
+    String::from_utf8(wtr.into_inner().expect("to be able to get vec"))
+        .expect("To be able to serialize CSV")
+}
+
+pub fn build_json_output(users: Vec<User>) -> String {
+    let mut outputs = Vec::new();
+
+    for user in users {
+        let problems = user.readiness_problems();
+
+        let mut methods = Vec::new();
+        for method in user.contact_methods {
+            methods.push(object! {
+                type: method.kind,
+                label: method.label,
+                address: method.address
+            });
+        }
+
+        let mut rules = Vec::new();
+        for rule in user.notification_rules {
+            rules.push(object! {
+                urgency: rule.urgency,
+                startDelayMinutes: rule.start_delay_in_minutes,
+                contactMethod: object! {
+                    type: rule.contact_method.kind,
+                    label: rule.contact_method.label
+                }
+            });
+        }
+
+        outputs.push(object! {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            role: user.role,
+            timeZone: user.time_zone,
+            jobTitle: user.job_title,
+            teams: user.teams,
+            contactMethods: methods,
+            notificationRules: rules,
+            problems: problems
+        });
+    }
+
+    json::stringify_pretty(outputs, 2)
+}
+
+pub fn build_tree_output(users: Vec<User>) -> String {
+    let tree = tree::TreePrinter::default();
+
+    for user in users {
+        let root = tree.add_line(format!(
+            "User - {} ({}) ({})",
+            user.name, user.email, user.id
+        ));
+        if let Some(role) = &user.role {
+            root.add_line(format!("Role - {}", role));
+        }
+        if let Some(time_zone) = &user.time_zone {
+            root.add_line(format!("Time Zone - {}", time_zone));
+        }
+        if let Some(job_title) = &user.job_title {
+            root.add_line(format!("Job Title - {}", job_title));
+        }
+
+        let methods = root.add_line("Contact Methods".into());
+        for method in user
+            .contact_methods
+            .iter()
+            .filter(|method| REACHABLE_BY.contains(&method.kind.as_str()))
+        {
+            methods.add_line(method.to_display());
+        }
+
+        for (name, urgency) in &[("High Urgency", "high"), ("Low Urgency", "low")] {
+            let rules = root.add_line(name.to_string());
+            for rule in rules_for(&user.notification_rules, urgency) {
+                rules.add_line(rule.to_display());
+            }
+        }
+
+        let problems = user.readiness_problems();
+        if !problems.is_empty() {
+            let node = root.add_line("Not Ready".into());
+            for problem in problems {
+                node.add_line(problem.to_string());
+            }
+        }
+    }
+
+    tree.render()
+}
+
+fn rules_for<'a>(
+    rules: &'a [NotificationRule],
+    urgency: &'a str,
+) -> impl Iterator<Item = &'a NotificationRule> {
+    rules.iter().filter(move |rule| rule.urgency == urgency)
+}
+
+/// `work (+1 5555550123)`, or just the label when there's no address.
+fn method_display(method: &ContactMethod) -> String {
+    match &method.address {
+        Some(address) => format!("{} ({})", method.label, address),
+        None => method.label.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn users_without_a_phone_or_high_urgency_rule_are_flagged() {
+        let sms = ContactMethod {
+            kind: "sms".into(),
+            label: "cell".into(),
+            address: Some("+44 5555550142".into()),
+        };
+        let users = vec![User {
+            id: "P2DQNMZ".into(),
+            name: "Alf Rowe".into(),
+            email: "alf@example.com".into(),
+            role: Some("user".into()),
+            time_zone: Some("Europe/London".into()),
+            job_title: None,
+            description: None,
+            teams: vec![],
+            contact_methods: vec![sms.clone()],
+            notification_rules: vec![NotificationRule {
+                urgency: "low".into(),
+                start_delay_in_minutes: 5,
+                contact_method: sms,
+            }],
+        }];
+
+        let output = build_csv_output(users);
+        let rows: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            vec![
+                "User ID,Name,Email,Role,Time Zone,Job Title,Phone,SMS,Push,High Urgency Rules,Low Urgency Rules,Problems",
+                "P2DQNMZ,Alf Rowe,alf@example.com,user,Europe/London,,,cell (+44 5555550142),,,after 5m - sms - cell,\"no phone contact method, no high-urgency notification rule\"",
+            ],
+            rows
+        );
+    }
+}
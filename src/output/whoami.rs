@@ -0,0 +1,66 @@
+use json::object;
+
+use crate::v2::TokenInfo;
+
+use super::tree;
+
+pub fn build_json_output(info: TokenInfo) -> String {
+    let read_only = info.is_read_only();
+    let token_type = if info.user.is_some() {
+        "user"
+    } else {
+        "account"
+    };
+    let user = info.user.map(|user| {
+        object! {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            role: user.role
+        }
+    });
+
+    json::stringify_pretty(
+        object! {
+            tokenType: token_type,
+            user: user,
+            readOnly: read_only,
+            subdomain: info.subdomain,
+            abilities: info.abilities
+        },
+        2,
+    )
+}
+
+pub fn build_tree_output(info: TokenInfo) -> String {
+    let tree = tree::TreePrinter::default();
+
+    let read_only = info.is_read_only();
+    match info.user {
+        Some(user) => {
+            tree.add_line("Token - user token".into());
+            let node = tree.add_line(format!("User - {} ({})", user.name, user.email));
+            if let Some(role) = user.role {
+                if read_only {
+                    node.add_line(format!("Role - {} (can't make changes)", role));
+                } else {
+                    node.add_line(format!("Role - {}", role));
+                }
+            }
+        }
+        None => {
+            tree.add_line("Token - account token".into());
+        }
+    }
+
+    if let Some(subdomain) = info.subdomain {
+        tree.add_line(format!("Account - {}", subdomain));
+    }
+
+    let abilities = tree.add_line(format!("Abilities - {}", info.abilities.len()));
+    for ability in info.abilities {
+        abilities.add_line(ability);
+    }
+
+    tree.render()
+}
@@ -6,7 +6,6 @@ pub struct ProgressBarHelper {
 
 pub enum ProgressBarType<'a> {
     SizedProgressBar(usize, &'a str),
-    #[allow(dead_code)]
     UnsizedProgressBar(&'a str),
 }
 
@@ -43,6 +42,10 @@ impl ProgressBarHelper {
         self.pb.inc_length(1);
     }
 
+    pub fn inc_length_by(&self, delta: usize) {
+        self.pb.inc_length(delta as u64);
+    }
+
     #[allow(dead_code)]
     pub fn inc_with_message(&self, message: &str) {
         self.pb.inc(1);
@@ -1,19 +1,52 @@
 use crate::progress::{ProgressBarHelper, ProgressBarType};
-use log::{error, info, warn};
-use reqwest::Client;
+use futures::future::join_all;
+use log::{debug, info, warn};
+use reqwest::{Client, Method, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use serde_json::json;
 use std::collections::BTreeMap;
+use tokio::sync::Semaphore;
+
+use super::cache::ResponseCache;
+use super::retry::{backoff_delay, is_retryable, retry_after_seconds, RequestOptions};
+use super::PagerDutyError;
 
 const PAGE_SIZE: u32 = 100;
+/// PagerDuty refuses offset based requests past this many records.
+const OFFSET_CEILING: u32 = 10_000;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct UserModel {
     pub name: String,
     pub id: String,
-    #[serde(rename = "self")]
-    pub self_ref: String,
     pub html_url: String,
     pub email: String,
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub job_title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub teams: Vec<ModelReference>,
+    #[serde(default)]
+    pub contact_methods: Vec<ContactMethodModel>,
+    #[serde(default)]
+    pub notification_rules: Vec<NotificationRuleModel>,
+}
+
+/// Only has an urgency and contact method when fetched with `include[]=notification_rules`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct NotificationRuleModel {
+    #[serde(default)]
+    pub urgency: Option<String>,
+    #[serde(default)]
+    pub start_delay_in_minutes: u32,
+    #[serde(default)]
+    pub contact_method: Option<ContactMethodModel>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -21,6 +54,69 @@ pub struct ServiceModel {
     pub name: String,
     pub id: String,
     pub escalation_policy: ModelReference,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub acknowledgement_timeout: Option<u64>,
+    #[serde(default)]
+    pub auto_resolve_timeout: Option<u64>,
+    #[serde(default)]
+    pub alert_creation: Option<String>,
+    #[serde(default)]
+    pub teams: Vec<ModelReference>,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub incident_urgency_rule: Option<UrgencyRuleModel>,
+    #[serde(default)]
+    pub alert_grouping: Option<String>,
+    #[serde(default)]
+    pub alert_grouping_timeout: Option<u64>,
+    #[serde(default)]
+    pub alert_grouping_parameters: Option<AlertGroupingParametersModel>,
+    #[serde(default)]
+    pub integrations: Vec<IntegrationModel>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UrgencyRuleModel {
+    #[serde(rename = "type")]
+    pub rule_type: String,
+    #[serde(default)]
+    pub urgency: Option<String>,
+    #[serde(default)]
+    pub during_support_hours: Option<Box<UrgencyRuleModel>>,
+    #[serde(default)]
+    pub outside_support_hours: Option<Box<UrgencyRuleModel>>,
+}
+
+/// The newer form of `alert_grouping` and `alert_grouping_timeout`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AlertGroupingParametersModel {
+    #[serde(rename = "type", default)]
+    pub grouping_type: Option<String>,
+    #[serde(default)]
+    pub config: Option<AlertGroupingConfigModel>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AlertGroupingConfigModel {
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+/// A reference unless fetched with `include[]=integrations`, which adds the vendor and key.
+#[derive(Clone, Debug, Deserialize)]
+pub struct IntegrationModel {
+    pub id: String,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub vendor: Option<ModelReference>,
+    #[serde(default)]
+    pub integration_key: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -28,15 +124,228 @@ pub struct EscalationPolicyModel {
     pub id: String,
     pub description: Option<String>,
     pub name: String,
+    #[serde(default)]
+    pub num_loops: u32,
+    #[serde(default)]
+    pub teams: Vec<ModelReference>,
+    #[serde(default)]
+    pub escalation_rules: Vec<EscalationRuleModel>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EscalationRuleModel {
+    pub escalation_delay_in_minutes: u32,
+    #[serde(default)]
+    pub targets: Vec<TargetModel>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TargetModel {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub target_type: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TeamModel {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TeamMemberModel {
+    pub user: ModelReference,
+    pub role: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ModelReference {
     id: String,
+    #[serde(default)]
+    summary: Option<String>,
+}
+
+impl ModelReference {
+    fn summary_or_id(&self) -> String {
+        self.summary.clone().unwrap_or_else(|| self.id.clone())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IncidentModel {
+    pub id: String,
+    pub incident_number: u64,
+    pub title: String,
+    pub status: String,
+    pub urgency: String,
+    pub created_at: String,
+    pub html_url: String,
+    pub service: ModelReference,
+    pub escalation_policy: ModelReference,
+    #[serde(default)]
+    pub assignments: Vec<AssignmentModel>,
+}
+
+/// A reference when listed on a user, the full method from `/users/{id}/contact_methods` or
+/// with `include[]=contact_methods`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ContactMethodModel {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub method_type: String,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub country_code: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContactMethodsResponseWrapper {
+    contact_methods: Vec<ContactMethodModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IncidentResponseWrapper {
+    incident: IncidentModel,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AssignmentModel {
+    pub assignee: ModelReference,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScheduleModel {
+    pub id: String,
+    pub name: String,
+    pub time_zone: String,
+    pub description: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub schedule_layers: Vec<ScheduleLayerModel>,
+    pub final_schedule: Option<RenderedScheduleModel>,
+    #[serde(default)]
+    pub teams: Vec<ModelReference>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScheduleLayerModel {
+    pub id: String,
+    pub name: String,
+    pub start: String,
+    pub end: Option<String>,
+    #[serde(default)]
+    pub rotation_virtual_start: Option<String>,
+    pub rotation_turn_length_seconds: i64,
+    #[serde(default)]
+    pub users: Vec<ScheduleLayerUserModel>,
+    #[serde(default)]
+    pub restrictions: Vec<RestrictionModel>,
+    #[serde(default)]
+    pub rendered_schedule_entries: Vec<ScheduleEntryModel>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RestrictionModel {
+    #[serde(rename = "type")]
+    pub restriction_type: String,
+    pub start_time_of_day: String,
+    pub duration_seconds: u64,
+    #[serde(default)]
+    pub start_day_of_week: Option<u8>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScheduleLayerUserModel {
+    pub user: ModelReference,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RenderedScheduleModel {
+    #[serde(default)]
+    pub rendered_schedule_entries: Vec<ScheduleEntryModel>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScheduleEntryModel {
+    pub start: String,
+    pub end: String,
+    pub user: ModelReference,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserResponseWrapper {
+    pub user: UserModel,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UsersResponseWrapper {
+    #[serde(default)]
+    pub users: Vec<UserModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AbilitiesResponseWrapper {
+    #[serde(default)]
+    pub abilities: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MaintenanceWindowModel {
+    pub id: String,
+    #[serde(default)]
+    pub sequence_number: u64,
+    pub start_time: String,
+    pub end_time: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub services: Vec<ModelReference>,
+    #[serde(default)]
+    pub teams: Vec<ModelReference>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceWindowResponseWrapper {
+    pub maintenance_window: MaintenanceWindowModel,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct OverrideModel {
+    pub id: String,
+    pub start: String,
+    pub end: String,
+    pub user: ModelReference,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverrideResponseWrapper {
+    #[serde(rename = "override")]
+    pub override_model: OverrideModel,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverridesResponseWrapper {
+    pub overrides: Vec<OverrideModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleResponseWrapper {
+    pub schedule: ScheduleModel,
 }
 
 pub(crate) struct PagerDutyApi {
     auth_token: String,
+    api_url: String,
+    options: RequestOptions,
+    cache: ResponseCache,
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,8 +353,22 @@ pub struct PagerDutyResponseWrapper {
     #[serde(flatten)]
     pub obj: PagerDutyObjects,
     pub limit: u32,
+    #[serde(default)]
     pub offset: u32,
+    #[serde(default)]
     pub more: bool,
+    #[serde(default)]
+    pub total: Option<u32>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+enum PageRequest {
+    First,
+    Offset(u32),
+    /// The first page without an offset, so endpoints that support cursors hand one back.
+    CursorStart,
+    Cursor(String),
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -53,6 +376,9 @@ pub struct OnCallModel {
     pub escalation_policy: ModelReference,
     pub escalation_level: u8,
     pub user: ModelReference,
+    pub schedule: Option<ModelReference>,
+    pub start: Option<String>,
+    pub end: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,6 +391,28 @@ pub enum PagerDutyObjects {
     Users(Vec<UserModel>),
     #[serde(rename = "services")]
     Services(Vec<ServiceModel>),
+    #[serde(rename = "incidents")]
+    Incidents(Vec<IncidentModel>),
+    #[serde(rename = "schedules")]
+    Schedules(Vec<ScheduleModel>),
+    #[serde(rename = "maintenance_windows")]
+    MaintenanceWindows(Vec<MaintenanceWindowModel>),
+    #[serde(rename = "teams")]
+    Teams(Vec<TeamModel>),
+    #[serde(rename = "members")]
+    Members(Vec<TeamMemberModel>),
+}
+
+/// Index records by `key`, so references can be resolved without another request.
+fn make_lookup_map<T, F>(records: Vec<T>, key: F) -> BTreeMap<String, T>
+where
+    F: Fn(&T) -> String,
+{
+    let mut map = BTreeMap::new();
+    for record in records {
+        map.insert(key(&record), record);
+    }
+    map
 }
 
 fn make_escalation_policies(
@@ -75,10 +423,7 @@ fn make_escalation_policies(
 ) -> Vec<super::EscalationPolicy> {
     let mut return_policies = Vec::new();
 
-    let mut users_map = BTreeMap::new();
-    for user in source_users {
-        users_map.insert(user.id.clone(), user);
-    }
+    let users_map = make_lookup_map(source_users, |user| user.id.clone());
 
     for esc_model in source_escalation_policies {
         let mut users = Vec::new();
@@ -108,6 +453,10 @@ fn make_escalation_policies(
                             id: user.id.clone(),
                             name: user.name.clone(),
                             email: user.email.clone(),
+                            shift_start: oncall.start.clone(),
+                            shift_end: oncall.end.clone(),
+                            schedule: oncall.schedule.as_ref().map(|s| s.summary_or_id()),
+                            teams: user.teams.iter().map(|team| team.id.clone()).collect(),
                         });
                     }
                 }
@@ -131,237 +480,1745 @@ fn make_escalation_policies(
             policy_name: esc_model.name.clone(),
             oncall_groups: users,
             services,
+            num_loops: esc_model.num_loops,
+            teams: esc_model.teams.iter().map(|team| team.id.clone()).collect(),
+            rules: esc_model
+                .escalation_rules
+                .iter()
+                .map(|rule| super::EscalationRule {
+                    escalation_delay_in_minutes: rule.escalation_delay_in_minutes,
+                    targets: rule
+                        .targets
+                        .iter()
+                        .map(|target| super::EscalationTarget {
+                            id: target.id.clone(),
+                            target_type: target.target_type.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
         });
     }
 
     return_policies
 }
 
-impl PagerDutyApi {
-    pub(crate) fn new(auth_token: String) -> Self {
-        PagerDutyApi { auth_token }
+fn make_incident(model: IncidentModel) -> super::Incident {
+    super::Incident {
+        id: model.id,
+        incident_number: model.incident_number,
+        title: model.title,
+        status: model.status,
+        urgency: model.urgency,
+        created_at: model.created_at,
+        html_url: model.html_url,
+        service_id: model.service.id.clone(),
+        service_name: model.service.summary_or_id(),
+        escalation_policy_name: model.escalation_policy.summary_or_id(),
+        assignees: model
+            .assignments
+            .iter()
+            .map(|assignment| assignment.assignee.summary_or_id())
+            .collect(),
     }
+}
 
-    pub(crate) async fn get_escalation_policies(&self) -> Vec<super::EscalationPolicy> {
-        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
-            0,
-            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching data from PagerDuty",
-        ));
-
-        let api_resolver = ApiResolver::new(&self.auth_token, &pb);
-
-        let (policies, oncalls, users, services) = tokio::join!(
-            self.fetch_policies_for_account(&api_resolver),
-            self.fetch_oncalls_for_account(&api_resolver),
-            self.fetch_users_for_account(&api_resolver),
-            self.fetch_services_for_account(&api_resolver)
-        );
-
-        pb.done();
-
-        make_escalation_policies(policies, users, oncalls, services)
+fn make_user(model: &UserModel) -> super::PagerDutyUser {
+    super::PagerDutyUser {
+        id: model.id.clone(),
+        name: model.name.clone(),
+        email: model.email.clone(),
+        shift_start: None,
+        shift_end: None,
+        schedule: None,
+        teams: model.teams.iter().map(|team| team.id.clone()).collect(),
     }
+}
 
-    async fn fetch_services_for_account(
-        &self,
-        api_resolver: &ApiResolver<'_>,
-    ) -> Vec<ServiceModel> {
-        let some_response = api_resolver
-            .make_api_call("https://api.pagerduty.com/services", &[])
-            .await;
+/// Group a single user's oncalls into the policies and levels they appear on.
+fn make_user_policies(
+    user: &super::PagerDutyUser,
+    source_oncalls: Vec<OnCallModel>,
+) -> Vec<super::EscalationPolicy> {
+    let mut policies: BTreeMap<String, super::EscalationPolicy> = BTreeMap::new();
 
-        let mut outputs: Vec<ServiceModel> = Vec::new();
+    for oncall in source_oncalls {
+        let policy = policies
+            .entry(oncall.escalation_policy.id.clone())
+            .or_insert_with(|| super::EscalationPolicy {
+                id: oncall.escalation_policy.id.clone(),
+                description: None,
+                policy_name: oncall.escalation_policy.summary_or_id(),
+                oncall_groups: Vec::new(),
+                services: Vec::new(),
+                num_loops: 0,
+                teams: Vec::new(),
+                rules: Vec::new(),
+            });
 
-        match some_response {
-            Some(objs) => {
-                for obj in objs {
-                    if let PagerDutyObjects::Services(services) = obj {
-                        for service in services {
-                            outputs.push(service);
-                        }
-                    }
-                }
-            }
-            None => {
-                warn!("Unable to get policies. Skipping.");
-            }
+        let shift = super::PagerDutyUser {
+            shift_start: oncall.start.clone(),
+            shift_end: oncall.end.clone(),
+            schedule: oncall.schedule.as_ref().map(|s| s.summary_or_id()),
+            ..user.clone()
+        };
+
+        match policy
+            .oncall_groups
+            .iter_mut()
+            .find(|group| group.depth == oncall.escalation_level)
+        {
+            Some(group) => group.users.push(shift),
+            None => policy.oncall_groups.push(super::PagerDutyUserGroups {
+                users: vec![shift],
+                depth: oncall.escalation_level,
+            }),
         }
-        outputs
     }
 
-    async fn fetch_users_for_account(&self, api_resolver: &ApiResolver<'_>) -> Vec<UserModel> {
-        let some_response = api_resolver
-            .make_api_call("https://api.pagerduty.com/users", &[])
-            .await;
+    let mut policies: Vec<super::EscalationPolicy> = policies.into_values().collect();
+    for policy in policies.iter_mut() {
+        policy.oncall_groups.sort();
+    }
+    policies.sort();
+    policies
+}
 
-        let mut outputs: Vec<UserModel> = Vec::new();
+fn make_schedule_entry(model: &ScheduleEntryModel) -> super::ScheduleEntry {
+    super::ScheduleEntry {
+        start: model.start.clone(),
+        end: model.end.clone(),
+        user_id: model.user.id.clone(),
+        user_name: model.user.summary_or_id(),
+    }
+}
 
-        match some_response {
-            Some(objs) => {
-                for obj in objs {
-                    if let PagerDutyObjects::Users(users) = obj {
-                        for user in users {
-                            outputs.push(user);
-                        }
-                    }
-                }
-            }
-            None => {
-                warn!("Unable to get policies. Skipping.");
-            }
-        }
-        outputs
+fn make_schedule(model: ScheduleModel) -> super::Schedule {
+    super::Schedule {
+        id: model.id,
+        name: model.name,
+        time_zone: model.time_zone,
+        description: model.description,
+        html_url: model.html_url,
+        teams: model.teams.iter().map(|team| team.id.clone()).collect(),
+        layers: model
+            .schedule_layers
+            .iter()
+            .map(|layer| super::ScheduleLayer {
+                id: layer.id.clone(),
+                name: layer.name.clone(),
+                start: layer.start.clone(),
+                end: layer.end.clone(),
+                rotation_virtual_start: layer.rotation_virtual_start.clone(),
+                rotation_turn_length_seconds: layer.rotation_turn_length_seconds,
+                users: layer
+                    .users
+                    .iter()
+                    .map(|user| user.user.summary_or_id())
+                    .collect(),
+                user_ids: layer
+                    .users
+                    .iter()
+                    .map(|user| user.user.id.clone())
+                    .collect(),
+                restrictions: layer
+                    .restrictions
+                    .iter()
+                    .map(|restriction| super::LayerRestriction {
+                        restriction_type: restriction.restriction_type.clone(),
+                        start_time_of_day: restriction.start_time_of_day.clone(),
+                        duration_seconds: restriction.duration_seconds,
+                        start_day_of_week: restriction.start_day_of_week,
+                    })
+                    .collect(),
+                entries: layer
+                    .rendered_schedule_entries
+                    .iter()
+                    .map(make_schedule_entry)
+                    .collect(),
+            })
+            .collect(),
+        final_schedule: model
+            .final_schedule
+            .map(|rendered| {
+                rendered
+                    .rendered_schedule_entries
+                    .iter()
+                    .map(make_schedule_entry)
+                    .collect()
+            })
+            .unwrap_or_default(),
     }
+}
 
-    async fn fetch_oncalls_for_account(&self, api_resolver: &ApiResolver<'_>) -> Vec<OnCallModel> {
-        let some_response = api_resolver
-            .make_api_call("https://api.pagerduty.com/oncalls", &["targets"])
-            .await;
+fn make_service(model: ServiceModel) -> super::Service {
+    let escalation_policy_name = model.escalation_policy.summary_or_id();
+    super::Service {
+        id: model.id,
+        name: model.name,
+        description: model.description,
+        escalation_policy_id: model.escalation_policy.id,
+        acknowledgement_timeout: model.acknowledgement_timeout,
+        auto_resolve_timeout: model.auto_resolve_timeout,
+        alert_creation: model.alert_creation,
+        teams: model.teams.into_iter().map(|team| team.id).collect(),
+        status: model.status,
+        escalation_policy_name,
+        urgency: model
+            .incident_urgency_rule
+            .as_ref()
+            .map(make_urgency_rule)
+            .unwrap_or_else(|| super::UrgencyRule::Constant("high".into())),
+        alert_grouping: make_alert_grouping(
+            model.alert_grouping,
+            model.alert_grouping_timeout,
+            model.alert_grouping_parameters,
+        ),
+        integrations: model
+            .integrations
+            .into_iter()
+            .map(|integration| super::Integration {
+                name: integration
+                    .name
+                    .or(integration.summary)
+                    .unwrap_or_else(|| "Unnamed integration".into()),
+                id: integration.id,
+                vendor: integration.vendor.map(|vendor| vendor.summary_or_id()),
+                key: integration.integration_key,
+            })
+            .collect(),
+    }
+}
 
-        let mut outputs: Vec<OnCallModel> = Vec::new();
+fn make_urgency_rule(model: &UrgencyRuleModel) -> super::UrgencyRule {
+    let urgency = |rule: &Option<Box<UrgencyRuleModel>>| {
+        rule.as_ref()
+            .and_then(|rule| rule.urgency.clone())
+            .unwrap_or_else(|| "high".into())
+    };
 
-        match some_response {
-            Some(objs) => {
-                for obj in objs {
-                    if let PagerDutyObjects::Oncalls(oncalls) = obj {
-                        for oncall in oncalls {
-                            outputs.push(oncall);
-                        }
-                    }
-                }
-            }
-            None => {
-                warn!("Unable to get policies. Skipping.");
-            }
-        }
-        outputs
+    match model.rule_type.as_str() {
+        "use_support_hours" => super::UrgencyRule::SupportHours {
+            during: urgency(&model.during_support_hours),
+            outside: urgency(&model.outside_support_hours),
+        },
+        _ => super::UrgencyRule::Constant(model.urgency.clone().unwrap_or_else(|| "high".into())),
     }
+}
 
-    async fn fetch_policies_for_account(
-        &self,
-        api_resolver: &ApiResolver<'_>,
-    ) -> Vec<EscalationPolicyModel> {
-        let some_response = api_resolver
-            .make_api_call(
-                "https://api.pagerduty.com/escalation_policies",
-                &["targets"],
-            )
-            .await;
-
-        let mut outputs: Vec<EscalationPolicyModel> = Vec::new();
+/// Prefer `alert_grouping_parameters`, accounts moved to it only leave the old fields empty.
+fn make_alert_grouping(
+    grouping: Option<String>,
+    timeout: Option<u64>,
+    parameters: Option<AlertGroupingParametersModel>,
+) -> Option<super::AlertGrouping> {
+    match parameters {
+        Some(AlertGroupingParametersModel {
+            grouping_type: Some(kind),
+            config,
+        }) => Some(super::AlertGrouping {
+            kind,
+            timeout: config.and_then(|config| config.timeout),
+        }),
+        _ => grouping.map(|kind| super::AlertGrouping { kind, timeout }),
+    }
+}
 
-        match some_response {
-            Some(objs) => {
-                for obj in objs {
-                    if let PagerDutyObjects::EscalationPolicies(policies) = obj {
-                        for policy in policies {
-                            outputs.push(policy);
-                        }
-                    }
-                }
-            }
-            None => {
-                warn!("Unable to get policies. Skipping.");
-            }
+fn make_contact_method(model: ContactMethodModel) -> super::ContactMethod {
+    let kind = match model
+        .method_type
+        .trim_end_matches("_reference")
+        .trim_end_matches("_contact_method")
+    {
+        "push_notification" => "push".to_string(),
+        kind => kind.to_string(),
+    };
+    let address = match (model.country_code, model.address) {
+        (Some(code), Some(address)) if kind == "phone" || kind == "sms" => {
+            Some(format!("+{} {}", code, address))
         }
+        (_, address) => address,
+    };
 
-        outputs
+    super::ContactMethod {
+        kind,
+        label: model.label.or(model.summary).unwrap_or(model.id),
+        address,
     }
 }
 
-struct ApiResolver<'a> {
-    pb: &'a ProgressBarHelper,
-    client: Client,
-    auth_token: &'a str,
+/// `acme` from `https://acme.pagerduty.com/users/PXXXXXX`.
+fn subdomain(html_url: &str) -> Option<String> {
+    let host = html_url.split("://").nth(1)?.split('/').next()?;
+    host.split('.')
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(String::from)
 }
 
-impl<'a> ApiResolver<'a> {
-    pub(crate) fn new(auth_token: &'a str, pb: &'a ProgressBarHelper) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .expect("Should be able to make client");
+fn make_account_user(model: UserModel) -> super::User {
+    // A rule only references its contact method, the user's own list has the details.
+    let methods = make_lookup_map(model.contact_methods.clone(), |method| method.id.clone());
+    let notification_rules = model
+        .notification_rules
+        .into_iter()
+        .filter_map(|rule| {
+            let method = rule.contact_method?;
+            Some(super::NotificationRule {
+                urgency: rule.urgency?,
+                start_delay_in_minutes: rule.start_delay_in_minutes,
+                contact_method: make_contact_method(
+                    methods.get(&method.id).cloned().unwrap_or(method),
+                ),
+            })
+        })
+        .collect();
 
-        ApiResolver {
-            pb,
-            client,
-            auth_token,
-        }
+    super::User {
+        id: model.id,
+        name: model.name,
+        email: model.email,
+        role: model.role,
+        time_zone: model.time_zone,
+        job_title: model.job_title,
+        description: model.description,
+        teams: model.teams.into_iter().map(|team| team.id).collect(),
+        contact_methods: model
+            .contact_methods
+            .into_iter()
+            .map(make_contact_method)
+            .collect(),
+        notification_rules,
     }
+}
 
-    async fn make_api_call(&self, url: &str, includes: &[&str]) -> Option<Vec<PagerDutyObjects>> {
-        let mut poll_queue: Vec<u32> = vec![0];
-        let mut response_array = Vec::new();
+fn make_team(model: TeamModel) -> super::Team {
+    super::Team {
+        id: model.id,
+        name: model.name,
+        description: model.description,
+    }
+}
 
-        while !poll_queue.is_empty() {
-            self.pb.inc_length();
-            let offset = poll_queue.pop().unwrap();
+fn make_override(model: OverrideModel) -> super::ScheduleOverride {
+    super::ScheduleOverride {
+        id: model.id,
+        start: model.start,
+        end: model.end,
+        user_name: model.user.summary_or_id(),
+        user_id: model.user.id,
+    }
+}
 
-            let resp = self
-                .client
-                .get(url)
-                .query(&[
-                    ("include[]", includes.join(",")),
-                    ("sort_by", "name".into()),
-                    ("limit", format!("{}", PAGE_SIZE)),
-                    ("offset", format!("{}", offset)),
-                ])
-                .header("Accept", "application/vnd.pagerduty+json;version=2")
-                .header("Authorization", format!("Token token={}", self.auth_token))
-                .send();
-
-            let resp = match resp.await {
-                Ok(body) => body,
-                Err(e) => {
-                    error!("Request to PagerDuty Failed: {}", e);
-                    return None;
-                }
-            };
+fn make_maintenance_window(model: MaintenanceWindowModel) -> super::MaintenanceWindow {
+    super::MaintenanceWindow {
+        id: model.id,
+        sequence_number: model.sequence_number,
+        start_time: model.start_time,
+        end_time: model.end_time,
+        description: model.description,
+        html_url: model.html_url,
+        services: model
+            .services
+            .iter()
+            .map(|service| service.summary_or_id())
+            .collect(),
+        teams: model.teams.into_iter().map(|team| team.id).collect(),
+    }
+}
 
-            let text_body = match resp.text().await {
-                Ok(body) => body,
-                Err(e) => {
-                    error!("Unable to get text from body: {}", e);
-                    return None;
-                }
-            };
+fn incident_action_request(
+    incident_id: &str,
+    action: &super::IncidentAction,
+) -> (Method, String, serde_json::Value) {
+    use super::IncidentAction;
 
-            let resp: PagerDutyResponseWrapper = match serde_json::from_str(&text_body) {
-                Ok(body) => body,
-                Err(e) => {
-                    error!("Unable to parse output from PagerDuty: {}", e);
-                    info!("Message: {}\n\n", text_body);
-                    return None;
-                }
-            };
+    let incident_path = format!("incidents/{}", incident_id);
+    let update = |changes: serde_json::Value| {
+        let mut incident = json!({ "type": "incident_reference" });
+        if let (Some(incident), Some(changes)) = (incident.as_object_mut(), changes.as_object()) {
+            incident.extend(changes.clone());
+        }
+        (
+            Method::PUT,
+            incident_path.clone(),
+            json!({ "incident": incident }),
+        )
+    };
 
-            response_array.push(resp.obj);
+    match action {
+        IncidentAction::Acknowledge => update(json!({ "status": "acknowledged" })),
+        IncidentAction::Resolve => update(json!({ "status": "resolved" })),
+        IncidentAction::ReassignToUser(user_id) => update(json!({
+            "assignments": [{ "assignee": { "id": user_id, "type": "user_reference" } }]
+        })),
+        IncidentAction::ReassignToPolicy(policy_id) => update(json!({
+            "escalation_policy": { "id": policy_id, "type": "escalation_policy_reference" }
+        })),
+        IncidentAction::Snooze(seconds) => (
+            Method::POST,
+            format!("{}/snooze", incident_path),
+            json!({ "duration": seconds }),
+        ),
+        IncidentAction::MergeInto(target_id) => (
+            Method::PUT,
+            format!("incidents/{}/merge", target_id),
+            json!({
+                "source_incidents": [{ "id": incident_id, "type": "incident_reference" }]
+            }),
+        ),
+    }
+}
 
-            if resp.more {
-                poll_queue.push(offset + PAGE_SIZE);
-            }
-            self.pb.inc();
+impl PagerDutyApi {
+    pub(crate) fn new(auth_token: String, api_url: String, options: RequestOptions) -> Self {
+        let api_url = api_url.trim_end_matches('/').to_string();
+        let cache = ResponseCache::new(&options.cache, &api_url, &auth_token);
+        PagerDutyApi {
+            auth_token,
+            api_url,
+            options,
+            cache,
         }
+    }
 
-        Some(response_array)
+    fn make_url(&self, path: &str) -> String {
+        format!("{}/{}", self.api_url, path)
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    pub(crate) async fn get_escalation_policies(
+        &self,
+        window: Option<&super::OncallWindow>,
+    ) -> Result<Vec<super::EscalationPolicy>, PagerDutyError> {
+        let (policies, _) = self.get_escalation_policies_and_services(window).await?;
+        Ok(policies)
+    }
 
-    fn do_parse<F>(path: &str, validate: F)
-    where
-        F: Fn(PagerDutyObjects) -> bool,
-    {
-        use std::fs::read_to_string;
-        use std::path::PathBuf;
+    /// The policies need every service anyway, so they're handed back too.
+    pub(crate) async fn get_escalation_policies_and_services(
+        &self,
+        window: Option<&super::OncallWindow>,
+    ) -> Result<(Vec<super::EscalationPolicy>, Vec<super::Service>), PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching data from PagerDuty",
+        ));
 
-        let mut ep = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        ep.push(format!("resources/test/{}", path));
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
 
-        let test_contents = read_to_string(ep).unwrap();
+        let (policies, oncalls, users, services) = tokio::join!(
+            self.fetch_policies_for_account(&api_resolver),
+            self.fetch_oncalls_for_account(&api_resolver, window),
+            self.fetch_users_for_account(&api_resolver, &[]),
+            self.fetch_services_for_account(&api_resolver)
+        );
+
+        pb.done();
+
+        let services = services?;
+        let policies = make_escalation_policies(policies?, users?, oncalls?, services.clone());
+        Ok((policies, services.into_iter().map(make_service).collect()))
+    }
+
+    pub(crate) async fn get_incidents(
+        &self,
+        query: &super::IncidentQuery,
+    ) -> Result<Vec<super::Incident>, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching incidents from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let mut params = Vec::new();
+        for status in &query.statuses {
+            params.push(("statuses[]", status.clone()));
+        }
+        for urgency in &query.urgencies {
+            params.push(("urgencies[]", urgency.clone()));
+        }
+        if let Some(since) = &query.since {
+            params.push(("since", since.clone()));
+        }
+        if let Some(until) = &query.until {
+            params.push(("until", until.clone()));
+        }
+        for team_id in &query.team_ids {
+            params.push(("team_ids[]", team_id.clone()));
+        }
+
+        let objs = api_resolver
+            .make_api_call(&self.make_url("incidents"), &[], &params)
+            .await;
+
+        pb.done();
+
+        let mut outputs = Vec::new();
+        for obj in objs? {
+            if let PagerDutyObjects::Incidents(incidents) = obj {
+                outputs.extend(incidents.into_iter().map(make_incident));
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    /// Fetch every schedule whose name passes `filter`, rendered between `since` and `until`.
+    pub(crate) async fn get_schedules<F>(
+        &self,
+        since: &str,
+        until: &str,
+        filter: F,
+    ) -> Result<Vec<super::Schedule>, PagerDutyError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching schedules from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let params = [("since", since.to_string()), ("until", until.to_string())];
+        let schedules = self
+            .fetch_schedules_for_account(&api_resolver, &params, filter)
+            .await;
+
+        pb.done();
+
+        Ok(schedules?.into_iter().map(make_schedule).collect())
+    }
+
+    /// Fetch every policy, service, schedule, team and user on the account.
+    pub(crate) async fn get_account(&self) -> Result<super::Account, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching account from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let (policies, users, services, teams, schedules) = tokio::join!(
+            self.fetch_policies_for_account(&api_resolver),
+            self.fetch_users_for_account(&api_resolver, &[]),
+            self.fetch_services_for_account(&api_resolver),
+            self.fetch_teams_for_account(&api_resolver),
+            self.fetch_schedules_for_account(&api_resolver, &[], |_| true)
+        );
+
+        pb.done();
+
+        let (users, services) = (users?, services?);
+
+        Ok(super::Account {
+            escalation_policies: make_escalation_policies(
+                policies?,
+                users.clone(),
+                Vec::new(),
+                services.clone(),
+            ),
+            services: services.into_iter().map(make_service).collect(),
+            schedules: schedules?.into_iter().map(make_schedule).collect(),
+            teams: teams?.into_iter().map(make_team).collect(),
+            users: users.into_iter().map(make_account_user).collect(),
+        })
+    }
+
+    pub(crate) async fn update_incidents(
+        &self,
+        from: &str,
+        incident_ids: &[String],
+        action: &super::IncidentAction,
+    ) -> Vec<super::IncidentActionResult> {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            incident_ids.len(),
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Updating incidents in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let update = |incident_id: &String| {
+            let (method, path, body) = incident_action_request(incident_id, action);
+            let api_resolver = &api_resolver;
+            let pb = &pb;
+            let incident_id = incident_id.clone();
+            async move {
+                let result = api_resolver
+                    .make_write_call(method, &self.make_url(&path), Some(from), &body)
+                    .await
+                    .map(|_| ());
+                pb.inc();
+                super::IncidentActionResult {
+                    incident_id,
+                    result,
+                }
+            }
+        };
+
+        let results = match action {
+            // Merging several incidents into the same target at once conflicts, so go one by one.
+            super::IncidentAction::MergeInto(_) => {
+                let mut results = Vec::new();
+                for incident_id in incident_ids {
+                    results.push(update(incident_id).await);
+                }
+                results
+            }
+            _ => join_all(incident_ids.iter().map(update)).await,
+        };
+
+        pb.done();
+
+        results
+    }
+
+    /// Find a user by ID, email or `me` for the owner of the API token.
+    pub(crate) async fn find_user(
+        &self,
+        user: &str,
+    ) -> Result<super::PagerDutyUser, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Looking up user in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let found = if user.contains('@') {
+            api_resolver
+                .make_api_call(&self.make_url("users"), &[], &[("query", user.into())])
+                .await
+                .map(|objs| {
+                    objs.into_iter()
+                        .filter_map(|obj| match obj {
+                            PagerDutyObjects::Users(users) => Some(users),
+                            _ => None,
+                        })
+                        .flatten()
+                        .find(|model| model.email.eq_ignore_ascii_case(user))
+                })
+        } else {
+            let url = self.make_url(&format!("users/{}", user));
+            match api_resolver
+                .make_get_call::<UserResponseWrapper>(&url, &[])
+                .await
+            {
+                Ok(wrapper) => Ok(Some(wrapper.user)),
+                Err(PagerDutyError::HttpStatus { status: 404, .. }) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+
+        pb.done();
+
+        match found? {
+            Some(model) => Ok(make_user(&model)),
+            None => Err(PagerDutyError::NotFound {
+                kind: "user",
+                name: user.to_string(),
+            }),
+        }
+    }
+
+    /// What the account can do, which any valid token can read.
+    pub(crate) async fn get_abilities(&self) -> Result<Vec<String>, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Fetching abilities from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let wrapper = api_resolver
+            .make_get_call::<AbilitiesResponseWrapper>(&self.make_url("abilities"), &[])
+            .await;
+
+        pb.done();
+
+        Ok(wrapper?.abilities)
+    }
+
+    /// Who owns the token, the account it's for and what the account can do.
+    pub(crate) async fn get_token_info(&self) -> Result<super::TokenInfo, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Checking the token with PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let (me_url, abilities_url) = (self.make_url("users/me"), self.make_url("abilities"));
+        let (me, abilities) = tokio::join!(
+            api_resolver.make_get_call::<UserResponseWrapper>(&me_url, &[]),
+            api_resolver.make_get_call::<AbilitiesResponseWrapper>(&abilities_url, &[])
+        );
+
+        let result = async {
+            // Account tokens don't belong to a user, so any user shows which account it is.
+            let user = match me {
+                Ok(wrapper) => Some(wrapper.user),
+                Err(PagerDutyError::HttpStatus { status: 400, .. }) => None,
+                Err(e) => return Err(e),
+            };
+            let html_url = match &user {
+                Some(user) => Some(user.html_url.clone()),
+                None => api_resolver
+                    .make_get_call::<UsersResponseWrapper>(
+                        &self.make_url("users"),
+                        &[("limit", "1".into())],
+                    )
+                    .await?
+                    .users
+                    .into_iter()
+                    .next()
+                    .map(|user| user.html_url),
+            };
+
+            Ok(super::TokenInfo {
+                user: user.map(make_account_user),
+                subdomain: html_url.as_deref().and_then(subdomain),
+                abilities: abilities?.abilities,
+            })
+        }
+        .await;
+
+        pb.done();
+
+        result
+    }
+
+    /// Find a schedule by ID or case insensitive name, rendered between `since` and `until`.
+    pub(crate) async fn find_schedule(
+        &self,
+        schedule: &str,
+        since: &str,
+        until: &str,
+    ) -> Result<super::Schedule, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Looking up schedule in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let objs = api_resolver
+            .make_api_call(
+                &self.make_url("schedules"),
+                &[],
+                &[("sort_by", "name".into())],
+            )
+            .await;
+
+        let mut schedules = Vec::new();
+        for obj in objs? {
+            if let PagerDutyObjects::Schedules(models) = obj {
+                schedules.extend(models);
+            }
+        }
+
+        // Names aren't unique, and changing the wrong rotation is worse than asking for an ID.
+        let mut matches: Vec<&ScheduleModel> = match schedules.iter().find(|m| m.id == schedule) {
+            Some(model) => vec![model],
+            None => schedules
+                .iter()
+                .filter(|model| model.name.to_lowercase() == schedule.to_lowercase())
+                .collect(),
+        };
+        let schedule_id = match matches.len() {
+            1 => matches.remove(0).id.clone(),
+            0 => {
+                pb.done();
+                return Err(PagerDutyError::NotFound {
+                    kind: "schedule",
+                    name: schedule.to_string(),
+                });
+            }
+            _ => {
+                pb.done();
+                return Err(PagerDutyError::Ambiguous {
+                    kind: "schedule",
+                    name: schedule.to_string(),
+                    matches: matches
+                        .iter()
+                        .map(|model| format!("{} ({})", model.name, model.id))
+                        .collect(),
+                });
+            }
+        };
+
+        let detail = api_resolver
+            .make_get_call::<ScheduleResponseWrapper>(
+                &self.make_url(&format!("schedules/{}", schedule_id)),
+                &[("since", since.to_string()), ("until", until.to_string())],
+            )
+            .await;
+
+        pb.done();
+
+        Ok(make_schedule(detail?.schedule))
+    }
+
+    pub(crate) async fn get_overrides(
+        &self,
+        schedule_id: &str,
+        since: &str,
+        until: &str,
+    ) -> Result<Vec<super::ScheduleOverride>, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Fetching overrides from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let resp = api_resolver
+            .make_get_call::<OverridesResponseWrapper>(
+                &self.make_url(&format!("schedules/{}/overrides", schedule_id)),
+                &[("since", since.to_string()), ("until", until.to_string())],
+            )
+            .await;
+
+        pb.done();
+
+        Ok(resp?.overrides.into_iter().map(make_override).collect())
+    }
+
+    pub(crate) async fn create_override(
+        &self,
+        schedule_id: &str,
+        start: &str,
+        end: &str,
+        user: &super::PagerDutyUser,
+    ) -> Result<super::ScheduleOverride, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Creating override in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let body = json!({
+            "override": {
+                "start": start,
+                "end": end,
+                "user": { "id": user.id, "type": "user_reference" }
+            }
+        });
+        let resp = api_resolver
+            .make_write_call(
+                Method::POST,
+                &self.make_url(&format!("schedules/{}/overrides", schedule_id)),
+                None,
+                &body,
+            )
+            .await;
+
+        pb.done();
+
+        let resp = resp?;
+        let wrapper: OverrideResponseWrapper = serde_json::from_value(resp.clone())
+            .map_err(|e| PagerDutyError::parse(e, &resp.to_string()))?;
+
+        Ok(make_override(wrapper.override_model))
+    }
+
+    pub(crate) async fn delete_override(
+        &self,
+        schedule_id: &str,
+        override_id: &str,
+    ) -> Result<(), PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Deleting override in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let resp = api_resolver
+            .make_write_call(
+                Method::DELETE,
+                &self.make_url(&format!(
+                    "schedules/{}/overrides/{}",
+                    schedule_id, override_id
+                )),
+                None,
+                &serde_json::Value::Null,
+            )
+            .await;
+
+        pb.done();
+
+        resp.map(|_| ())
+    }
+
+    /// Fetch every service whose name passes `filter`.
+    pub(crate) async fn get_services<F>(
+        &self,
+        filter: F,
+    ) -> Result<Vec<super::Service>, PagerDutyError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching services from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let services = self.fetch_services_for_account(&api_resolver).await;
+
+        pb.done();
+
+        Ok(services?
+            .into_iter()
+            .filter(|service| filter(&service.name))
+            .map(make_service)
+            .collect())
+    }
+
+    pub(crate) async fn find_team_ids(
+        &self,
+        teams: &[&str],
+    ) -> Result<Vec<String>, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Looking up teams in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let models = self.fetch_teams_for_account(&api_resolver).await;
+
+        pb.done();
+
+        let models = models?;
+        let by_id = make_lookup_map(models.clone(), |model| model.id.clone());
+        let by_name = make_lookup_map(models, |model| model.name.to_lowercase());
+
+        let mut team_ids = Vec::new();
+        for team in teams {
+            match by_id
+                .get(*team)
+                .or_else(|| by_name.get(&team.to_lowercase()))
+            {
+                Some(model) => team_ids.push(model.id.clone()),
+                None => {
+                    return Err(PagerDutyError::NotFound {
+                        kind: "team",
+                        name: team.to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(team_ids)
+    }
+
+    pub(crate) async fn get_team_details<F>(
+        &self,
+        filter: F,
+    ) -> Result<Vec<super::TeamDetails>, PagerDutyError>
+    where
+        F: Fn(&super::Team) -> bool,
+    {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching teams from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let (teams, policies, services) = tokio::join!(
+            self.fetch_teams_for_account(&api_resolver),
+            self.fetch_policies_for_account(&api_resolver),
+            self.fetch_services_for_account(&api_resolver)
+        );
+
+        let result = async {
+            let teams: Vec<super::Team> = teams?
+                .into_iter()
+                .map(make_team)
+                .filter(|team| filter(team))
+                .collect();
+            let (policies, services) = (policies?, services?);
+
+            let members = join_all(
+                teams
+                    .iter()
+                    .map(|team| self.fetch_members_for_team(&api_resolver, &team.id)),
+            )
+            .await;
+
+            let mut details = Vec::new();
+            for (team, members) in teams.into_iter().zip(members) {
+                let owned = |teams: &[ModelReference]| teams.iter().any(|t| t.id == team.id);
+                details.push(super::TeamDetails {
+                    members: members?
+                        .into_iter()
+                        .map(|member| super::TeamMember {
+                            id: member.user.id.clone(),
+                            name: member.user.summary_or_id(),
+                            role: member.role,
+                        })
+                        .collect(),
+                    escalation_policies: policies
+                        .iter()
+                        .filter(|policy| owned(&policy.teams))
+                        .map(|policy| policy.name.clone())
+                        .collect(),
+                    services: services
+                        .iter()
+                        .filter(|service| owned(&service.teams))
+                        .map(|service| service.name.clone())
+                        .collect(),
+                    team,
+                });
+            }
+
+            Ok(details)
+        }
+        .await;
+
+        pb.done();
+
+        result
+    }
+
+    pub(crate) async fn get_maintenance_windows(
+        &self,
+        filter: super::MaintenanceFilter,
+    ) -> Result<Vec<super::MaintenanceWindow>, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching maintenance windows from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let mut params = Vec::new();
+        match filter {
+            super::MaintenanceFilter::All => {}
+            super::MaintenanceFilter::Ongoing => params.push(("filter", "ongoing".to_string())),
+            super::MaintenanceFilter::Future => params.push(("filter", "future".to_string())),
+        }
+
+        let objs = api_resolver
+            .make_api_call(&self.make_url("maintenance_windows"), &[], &params)
+            .await;
+
+        pb.done();
+
+        let mut outputs = Vec::new();
+        for obj in objs? {
+            if let PagerDutyObjects::MaintenanceWindows(windows) = obj {
+                outputs.extend(windows.into_iter().map(make_maintenance_window));
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    pub(crate) async fn create_maintenance_window(
+        &self,
+        from: &str,
+        services: &[super::Service],
+        start: &str,
+        end: &str,
+        description: Option<&str>,
+    ) -> Result<super::MaintenanceWindow, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Creating maintenance window in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let service_refs: Vec<serde_json::Value> = services
+            .iter()
+            .map(|service| json!({ "id": service.id, "type": "service_reference" }))
+            .collect();
+        let body = json!({
+            "maintenance_window": {
+                "type": "maintenance_window",
+                "start_time": start,
+                "end_time": end,
+                "description": description,
+                "services": service_refs
+            }
+        });
+
+        let resp = api_resolver
+            .make_write_call(
+                Method::POST,
+                &self.make_url("maintenance_windows"),
+                Some(from),
+                &body,
+            )
+            .await;
+
+        pb.done();
+
+        let resp = resp?;
+        let wrapper: MaintenanceWindowResponseWrapper = serde_json::from_value(resp.clone())
+            .map_err(|e| PagerDutyError::parse(e, &resp.to_string()))?;
+
+        Ok(make_maintenance_window(wrapper.maintenance_window))
+    }
+
+    /// Every user that passes `filter`, with their contact methods and notification rules.
+    pub(crate) async fn get_users<F>(&self, filter: F) -> Result<Vec<super::User>, PagerDutyError>
+    where
+        F: Fn(&super::User) -> bool,
+    {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching users from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let users = self
+            .fetch_users_for_account(
+                &api_resolver,
+                &["contact_methods", "notification_rules", "teams"],
+            )
+            .await;
+
+        pb.done();
+
+        Ok(users?
+            .into_iter()
+            .map(make_account_user)
+            .filter(|user| filter(user))
+            .collect())
+    }
+
+    pub(crate) async fn get_contact_methods(
+        &self,
+        user_ids: &[String],
+    ) -> Result<BTreeMap<String, Vec<super::ContactMethod>>, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Fetching contact methods from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let urls: Vec<String> = user_ids
+            .iter()
+            .map(|id| self.make_url(&format!("users/{}/contact_methods", id)))
+            .collect();
+        let wrappers = join_all(
+            urls.iter()
+                .map(|url| api_resolver.make_get_call::<ContactMethodsResponseWrapper>(url, &[])),
+        )
+        .await;
+
+        pb.done();
+
+        let mut contact_methods = BTreeMap::new();
+        for (user_id, wrapper) in user_ids.iter().zip(wrappers) {
+            contact_methods.insert(
+                user_id.clone(),
+                wrapper?
+                    .contact_methods
+                    .into_iter()
+                    .map(make_contact_method)
+                    .collect(),
+            );
+        }
+
+        Ok(contact_methods)
+    }
+
+    pub(crate) async fn create_incident(
+        &self,
+        from: &str,
+        service: &super::Service,
+        title: &str,
+        urgency: &str,
+    ) -> Result<super::Incident, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Opening incident in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let body = json!({
+            "incident": {
+                "type": "incident",
+                "title": title,
+                "urgency": urgency,
+                "service": { "id": service.id, "type": "service_reference" }
+            }
+        });
+
+        let resp = api_resolver
+            .make_write_call(Method::POST, &self.make_url("incidents"), Some(from), &body)
+            .await;
+
+        pb.done();
+
+        let resp = resp?;
+        let wrapper: IncidentResponseWrapper = serde_json::from_value(resp.clone())
+            .map_err(|e| PagerDutyError::parse(e, &resp.to_string()))?;
+
+        Ok(make_incident(wrapper.incident))
+    }
+
+    pub(crate) async fn end_maintenance_window(
+        &self,
+        window_id: &str,
+    ) -> Result<(), PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::UnsizedProgressBar(
+            "{prefix:.bold.dim} {spinner:.green} Ending maintenance window in PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let resp = api_resolver
+            .make_write_call(
+                Method::DELETE,
+                &self.make_url(&format!("maintenance_windows/{}", window_id)),
+                None,
+                &serde_json::Value::Null,
+            )
+            .await;
+
+        pb.done();
+
+        resp.map(|_| ())
+    }
+
+    /// Every policy and level `user` is on, now or during `window`.
+    pub(crate) async fn get_oncalls_for_user(
+        &self,
+        user: &super::PagerDutyUser,
+        window: Option<&super::OncallWindow>,
+    ) -> Result<Vec<super::EscalationPolicy>, PagerDutyError> {
+        let pb = ProgressBarHelper::new(ProgressBarType::SizedProgressBar(
+            0,
+            "{prefix:.bold.dim} {spinner:.green} {pos:>2}/{len:>2} Fetching oncalls from PagerDuty",
+        ));
+
+        let api_resolver = ApiResolver::new(&self.auth_token, &self.options, &self.cache, &pb);
+
+        let mut params = vec![("user_ids[]", user.id.clone())];
+        if let Some(window) = window {
+            params.push(("since", window.since.clone()));
+            params.push(("until", window.until.clone()));
+        }
+
+        let objs = api_resolver
+            .make_api_call(&self.make_url("oncalls"), &[], &params)
+            .await;
+
+        pb.done();
+
+        let mut oncalls = Vec::new();
+        for obj in objs? {
+            if let PagerDutyObjects::Oncalls(models) = obj {
+                oncalls.extend(models);
+            }
+        }
+
+        Ok(make_user_policies(user, oncalls))
+    }
+
+    async fn fetch_services_for_account(
+        &self,
+        api_resolver: &ApiResolver<'_>,
+    ) -> Result<Vec<ServiceModel>, PagerDutyError> {
+        let objs = api_resolver
+            .make_api_call(
+                &self.make_url("services"),
+                &["integrations"],
+                &[("sort_by", "name".into())],
+            )
+            .await?;
+
+        let mut outputs: Vec<ServiceModel> = Vec::new();
+        for obj in objs {
+            if let PagerDutyObjects::Services(services) = obj {
+                outputs.extend(services);
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    /// List every schedule whose name passes `filter`, then fetch each one's layers.
+    async fn fetch_schedules_for_account<F>(
+        &self,
+        api_resolver: &ApiResolver<'_>,
+        params: &[(&str, String)],
+        filter: F,
+    ) -> Result<Vec<ScheduleModel>, PagerDutyError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let objs = api_resolver
+            .make_api_call(
+                &self.make_url("schedules"),
+                &[],
+                &[("sort_by", "name".into())],
+            )
+            .await?;
+
+        let mut schedule_ids = Vec::new();
+        for obj in objs {
+            if let PagerDutyObjects::Schedules(schedules) = obj {
+                for schedule in schedules {
+                    if filter(&schedule.name) {
+                        schedule_ids.push(schedule.id);
+                    }
+                }
+            }
+        }
+
+        api_resolver.pb.inc_length_by(schedule_ids.len());
+        let details = join_all(schedule_ids.iter().map(|id| async move {
+            let resp = api_resolver
+                .make_get_call::<ScheduleResponseWrapper>(
+                    &self.make_url(&format!("schedules/{}", id)),
+                    params,
+                )
+                .await;
+            api_resolver.pb.inc();
+            resp
+        }))
+        .await;
+
+        let mut outputs = Vec::new();
+        for detail in details {
+            outputs.push(detail?.schedule);
+        }
+
+        Ok(outputs)
+    }
+
+    async fn fetch_teams_for_account(
+        &self,
+        api_resolver: &ApiResolver<'_>,
+    ) -> Result<Vec<TeamModel>, PagerDutyError> {
+        let objs = api_resolver
+            .make_api_call(&self.make_url("teams"), &[], &[])
+            .await?;
+
+        let mut outputs: Vec<TeamModel> = Vec::new();
+        for obj in objs {
+            if let PagerDutyObjects::Teams(teams) = obj {
+                outputs.extend(teams);
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    async fn fetch_members_for_team(
+        &self,
+        api_resolver: &ApiResolver<'_>,
+        team_id: &str,
+    ) -> Result<Vec<TeamMemberModel>, PagerDutyError> {
+        let objs = api_resolver
+            .make_api_call(
+                &self.make_url(&format!("teams/{}/members", team_id)),
+                &[],
+                &[],
+            )
+            .await?;
+
+        let mut outputs: Vec<TeamMemberModel> = Vec::new();
+        for obj in objs {
+            if let PagerDutyObjects::Members(members) = obj {
+                outputs.extend(members);
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    async fn fetch_users_for_account(
+        &self,
+        api_resolver: &ApiResolver<'_>,
+        includes: &[&str],
+    ) -> Result<Vec<UserModel>, PagerDutyError> {
+        let objs = api_resolver
+            .make_api_call(
+                &self.make_url("users"),
+                includes,
+                &[("sort_by", "name".into())],
+            )
+            .await?;
+
+        let mut outputs: Vec<UserModel> = Vec::new();
+        for obj in objs {
+            if let PagerDutyObjects::Users(users) = obj {
+                outputs.extend(users);
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    async fn fetch_oncalls_for_account(
+        &self,
+        api_resolver: &ApiResolver<'_>,
+        window: Option<&super::OncallWindow>,
+    ) -> Result<Vec<OnCallModel>, PagerDutyError> {
+        let mut params = vec![("sort_by", "name".to_string())];
+        if let Some(window) = window {
+            params.push(("since", window.since.clone()));
+            params.push(("until", window.until.clone()));
+        }
+
+        let objs = api_resolver
+            .make_api_call(&self.make_url("oncalls"), &["targets"], &params)
+            .await?;
+
+        let mut outputs: Vec<OnCallModel> = Vec::new();
+        for obj in objs {
+            if let PagerDutyObjects::Oncalls(oncalls) = obj {
+                outputs.extend(oncalls);
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    async fn fetch_policies_for_account(
+        &self,
+        api_resolver: &ApiResolver<'_>,
+    ) -> Result<Vec<EscalationPolicyModel>, PagerDutyError> {
+        let objs = api_resolver
+            .make_api_call(
+                &self.make_url("escalation_policies"),
+                &["targets"],
+                &[("sort_by", "name".into())],
+            )
+            .await?;
+
+        let mut outputs: Vec<EscalationPolicyModel> = Vec::new();
+        for obj in objs {
+            if let PagerDutyObjects::EscalationPolicies(policies) = obj {
+                outputs.extend(policies);
+            }
+        }
+
+        Ok(outputs)
+    }
+}
+
+struct ApiResolver<'a> {
+    pb: &'a ProgressBarHelper,
+    client: Client,
+    auth_token: &'a str,
+    cache: &'a ResponseCache,
+    max_attempts: u32,
+    limiter: Semaphore,
+}
+
+impl<'a> ApiResolver<'a> {
+    pub(crate) fn new(
+        auth_token: &'a str,
+        options: &RequestOptions,
+        cache: &'a ResponseCache,
+        pb: &'a ProgressBarHelper,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Should be able to make client");
+
+        ApiResolver {
+            pb,
+            client,
+            auth_token,
+            cache,
+            max_attempts: options.max_attempts.max(1),
+            limiter: Semaphore::new(options.max_concurrent_requests.max(1)),
+        }
+    }
+
+    async fn make_api_call(
+        &self,
+        url: &str,
+        includes: &[&str],
+        params: &[(&str, String)],
+    ) -> Result<Vec<PagerDutyObjects>, PagerDutyError> {
+        self.pb.inc_length();
+        let first = self
+            .fetch_page(url, includes, params, PageRequest::First)
+            .await?;
+        self.pb.inc();
+
+        if first.next_cursor.is_some() {
+            return self.follow_cursors(url, includes, params, first).await;
+        }
+
+        let mut response_array = Vec::new();
+        let step = first.limit.max(1);
+        let mut next_offset = first.offset + step;
+        let more = first.more;
+        let total = first.total;
+        response_array.push(first.obj);
+
+        if !more {
+            return Ok(response_array);
+        }
+
+        match total {
+            Some(total) if total > OFFSET_CEILING => {
+                return self.fetch_past_ceiling(url, includes, params).await;
+            }
+            Some(total) => {
+                let offsets: Vec<u32> = (next_offset..total).step_by(step as usize).collect();
+                self.pb.inc_length_by(offsets.len());
+
+                let pages = join_all(offsets.into_iter().map(|offset| async move {
+                    let resp = self
+                        .fetch_page(url, includes, params, PageRequest::Offset(offset))
+                        .await;
+                    self.pb.inc();
+                    resp
+                }))
+                .await;
+
+                for page in pages {
+                    response_array.push(page?.obj);
+                }
+            }
+            None => loop {
+                self.pb.inc_length();
+                let resp = self
+                    .fetch_page(url, includes, params, PageRequest::Offset(next_offset))
+                    .await?;
+                self.pb.inc();
+
+                next_offset += resp.limit.max(1);
+                let more = resp.more;
+                response_array.push(resp.obj);
+
+                if !more {
+                    break;
+                }
+                if next_offset >= OFFSET_CEILING {
+                    return self.fetch_past_ceiling(url, includes, params).await;
+                }
+            },
+        }
+
+        Ok(response_array)
+    }
+
+    /// PagerDuty stops serving offsets at `OFFSET_CEILING`, so anything bigger has to be
+    /// fetched again with cursors. Endpoints without them can't be fetched completely.
+    async fn fetch_past_ceiling(
+        &self,
+        url: &str,
+        includes: &[&str],
+        params: &[(&str, String)],
+    ) -> Result<Vec<PagerDutyObjects>, PagerDutyError> {
+        self.pb.inc_length();
+        let first = self
+            .fetch_page(url, includes, params, PageRequest::CursorStart)
+            .await?;
+        self.pb.inc();
+
+        if first.next_cursor.is_none() {
+            return Err(PagerDutyError::TooManyRecords {
+                url: url.to_string(),
+                limit: OFFSET_CEILING,
+            });
+        }
+        self.follow_cursors(url, includes, params, first).await
+    }
+
+    async fn follow_cursors(
+        &self,
+        url: &str,
+        includes: &[&str],
+        params: &[(&str, String)],
+        first: PagerDutyResponseWrapper,
+    ) -> Result<Vec<PagerDutyObjects>, PagerDutyError> {
+        let mut next_cursor = first.next_cursor;
+        let mut response_array = vec![first.obj];
+
+        while let Some(cursor) = next_cursor {
+            self.pb.inc_length();
+            let resp = self
+                .fetch_page(url, includes, params, PageRequest::Cursor(cursor))
+                .await?;
+            next_cursor = resp.next_cursor;
+            response_array.push(resp.obj);
+            self.pb.inc();
+        }
+
+        Ok(response_array)
+    }
+
+    async fn fetch_page(
+        &self,
+        url: &str,
+        includes: &[&str],
+        params: &[(&str, String)],
+        page: PageRequest,
+    ) -> Result<PagerDutyResponseWrapper, PagerDutyError> {
+        // Array parameters are repeated, PagerDuty doesn't split a comma separated value.
+        let mut query: Vec<(&str, String)> = includes
+            .iter()
+            .map(|include| ("include[]", include.to_string()))
+            .collect();
+        query.push(("limit", format!("{}", PAGE_SIZE)));
+        query.extend(params.iter().cloned());
+        match page {
+            PageRequest::First => {
+                query.push(("offset", "0".into()));
+                query.push(("total", "true".into()));
+            }
+            PageRequest::Offset(offset) => query.push(("offset", format!("{}", offset))),
+            PageRequest::CursorStart => {}
+            PageRequest::Cursor(cursor) => query.push(("cursor", cursor)),
+        }
+
+        let text_body = self.get(url, &query).await?;
+
+        match serde_json::from_str(&text_body) {
+            Ok(body) => Ok(body),
+            Err(e) => {
+                info!("Message: {}\n\n", text_body);
+                Err(PagerDutyError::parse(e, &text_body))
+            }
+        }
+    }
+
+    /// Fetch a single object, like `/schedules/{id}`, that isn't paginated.
+    async fn make_get_call<T>(
+        &self,
+        url: &str,
+        params: &[(&str, String)],
+    ) -> Result<T, PagerDutyError>
+    where
+        T: DeserializeOwned,
+    {
+        let text_body = self.get(url, params).await?;
+
+        serde_json::from_str(&text_body).map_err(|e| PagerDutyError::parse(e, &text_body))
+    }
+
+    /// GET from the cache when possible, saving what is fetched.
+    async fn get(&self, url: &str, query: &[(&str, String)]) -> Result<String, PagerDutyError> {
+        if let Some(text_body) = self.cache.read(url, query)? {
+            return Ok(text_body);
+        }
+
+        let text_body = self
+            .send_with_retries(url, true, || self.request(Method::GET, url).query(query))
+            .await?;
+        self.cache.write(url, query, &text_body);
+
+        Ok(text_body)
+    }
+
+    /// Send a PUT, POST or DELETE, on behalf of `from` for endpoints where PagerDuty requires
+    /// a valid user's email.
+    async fn make_write_call(
+        &self,
+        method: Method,
+        url: &str,
+        from: Option<&str>,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, PagerDutyError> {
+        self.cache.before_write(url)?;
+
+        // Sending a POST twice could create two incidents, overrides or maintenance windows.
+        let idempotent = method != Method::POST;
+        let text_body = self
+            .send_with_retries(url, idempotent, || {
+                let mut request = self.request(method.clone(), url);
+                if !body.is_null() {
+                    request = request.json(body);
+                }
+                match from {
+                    Some(from) => request.header("From", from),
+                    None => request,
+                }
+            })
+            .await?;
+
+        if text_body.trim().is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+
+        serde_json::from_str(&text_body).map_err(|e| PagerDutyError::parse(e, &text_body))
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .header("Accept", "application/vnd.pagerduty+json;version=2")
+            .header("Authorization", format!("Token token={}", self.auth_token))
+    }
+
+    async fn send_with_retries<F>(
+        &self,
+        url: &str,
+        idempotent: bool,
+        make_request: F,
+    ) -> Result<String, PagerDutyError>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 1;
+        loop {
+            match self.send(url, make_request()).await {
+                Ok(body) => return Ok(body),
+                Err(e) if attempt < self.max_attempts && is_retryable(&e, idempotent) => {
+                    let delay = backoff_delay(attempt, &e);
+                    warn!(
+                        "{} (attempt {}/{}). Retrying in {:?}",
+                        e, attempt, self.max_attempts, delay
+                    );
+                    tokio::time::delay_for(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn send(&self, url: &str, request: RequestBuilder) -> Result<String, PagerDutyError> {
+        let resp = {
+            let _permit = self.limiter.acquire().await;
+            request.send().await?
+        };
+
+        let status = resp.status();
+        let retry_after = retry_after_seconds(&resp);
+        let text_body = resp.text().await?;
+
+        if !status.is_success() {
+            debug!("PagerDuty returned {} for {}", status, url);
+            return Err(PagerDutyError::from_status(
+                status.as_u16(),
+                &text_body,
+                retry_after,
+            ));
+        }
+
+        Ok(text_body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v2::IncidentAction;
+
+    fn do_parse<F>(path: &str, validate: F)
+    where
+        F: Fn(PagerDutyObjects) -> bool,
+    {
+        use std::fs::read_to_string;
+        use std::path::PathBuf;
+
+        let mut ep = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        ep.push(format!("resources/test/{}", path));
+
+        let test_contents = read_to_string(ep).unwrap();
 
         let wrapper = match serde_json::from_str::<PagerDutyResponseWrapper>(&test_contents) {
             Ok(v) => v,
@@ -416,4 +2273,148 @@ mod test {
             }
         });
     }
+
+    #[test]
+    fn validate_cursor_page() {
+        let page = r#"{"users": [], "limit": 100, "next_cursor": "abc123"}"#;
+        let wrapper = serde_json::from_str::<PagerDutyResponseWrapper>(page).unwrap();
+
+        assert_eq!(Some("abc123".to_string()), wrapper.next_cursor);
+        assert_eq!(None, wrapper.total);
+        assert!(!wrapper.more);
+    }
+
+    #[test]
+    fn validate_incidents() {
+        do_parse("incidents.json", |i| {
+            if let PagerDutyObjects::Incidents(incidents) = i {
+                incidents[2].assignments.len() == 2
+                    && incidents[2].service.summary_or_id() == "Service a eaque non"
+            } else {
+                false
+            }
+        });
+    }
+
+    #[test]
+    fn incident_actions_build_requests() {
+        let (method, path, body) = incident_action_request("PT4KHLK", &IncidentAction::Acknowledge);
+        assert_eq!(Method::PUT, method);
+        assert_eq!("incidents/PT4KHLK", path);
+        assert_eq!(
+            json!({"incident": {"type": "incident_reference", "status": "acknowledged"}}),
+            body
+        );
+
+        let (method, path, body) =
+            incident_action_request("PT4KHLK", &IncidentAction::Snooze(3600));
+        assert_eq!(Method::POST, method);
+        assert_eq!("incidents/PT4KHLK/snooze", path);
+        assert_eq!(json!({"duration": 3600}), body);
+
+        let (method, path, body) =
+            incident_action_request("PT4KHLK", &IncidentAction::MergeInto("PQ1GN4X".into()));
+        assert_eq!(Method::PUT, method);
+        assert_eq!("incidents/PQ1GN4X/merge", path);
+        assert_eq!(
+            json!({"source_incidents": [{"id": "PT4KHLK", "type": "incident_reference"}]}),
+            body
+        );
+    }
+
+    #[test]
+    fn validate_schedules() {
+        do_parse("schedules.json", |i| {
+            if let PagerDutyObjects::Schedules(schedules) = i {
+                schedules.len() == 3 && schedules[0].schedule_layers.is_empty()
+            } else {
+                false
+            }
+        });
+    }
+
+    #[test]
+    fn validate_maintenance_windows() {
+        do_parse("maintenance_windows.json", |obj| {
+            if let PagerDutyObjects::MaintenanceWindows(windows) = obj {
+                let windows: Vec<_> = windows.into_iter().map(make_maintenance_window).collect();
+                assert_eq!(2, windows.len());
+                assert_eq!(None, windows[1].description);
+                assert_eq!(
+                    vec![
+                        "Service a eaque non".to_string(),
+                        "Service accusantium nisi voluptatem".to_string()
+                    ],
+                    windows[1].services
+                );
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    #[test]
+    fn validate_teams() {
+        do_parse("teams.json", |obj| {
+            if let PagerDutyObjects::Teams(teams) = obj {
+                let teams: Vec<_> = teams.into_iter().map(make_team).collect();
+                assert_eq!(2, teams.len());
+                assert_eq!("Payments", teams[0].name);
+                assert_eq!(None, teams[1].description);
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    #[test]
+    fn validate_overrides() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("resources/test/overrides.json");
+        let contents = std::fs::read_to_string(path).unwrap();
+
+        let wrapper: OverridesResponseWrapper = serde_json::from_str(&contents).unwrap();
+        let overrides: Vec<_> = wrapper.overrides.into_iter().map(make_override).collect();
+
+        assert_eq!(2, overrides.len());
+        assert_eq!("PQ47DCP", overrides[0].id);
+        assert_eq!("Kristina Gerlach", overrides[0].user_name);
+    }
+
+    #[test]
+    fn validate_schedule_detail() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("resources/test/schedule.json");
+        let contents = std::fs::read_to_string(path).unwrap();
+
+        let wrapper: ScheduleResponseWrapper = serde_json::from_str(&contents).unwrap();
+        let schedule = make_schedule(wrapper.schedule);
+
+        assert_eq!(1, schedule.layers.len());
+        assert_eq!(86400, schedule.layers[0].rotation_turn_length_seconds);
+        assert_eq!(
+            vec![
+                "Earline Greenholt".to_string(),
+                "Kristina Gerlach".to_string()
+            ],
+            schedule.layers[0].users
+        );
+        assert_eq!(2, schedule.final_schedule.len());
+        assert_eq!("Kristina Gerlach", schedule.final_schedule[1].user_name);
+    }
+
+    #[test]
+    fn subdomain_comes_from_the_host() {
+        assert_eq!(
+            Some("acme".to_string()),
+            subdomain("https://acme.pagerduty.com/users/PXPGF42")
+        );
+        assert_eq!(
+            Some("acme".to_string()),
+            subdomain("https://acme.eu.pagerduty.com/users/PXPGF42")
+        );
+        assert_eq!(None, subdomain("not a url"));
+    }
 }
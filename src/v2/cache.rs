@@ -0,0 +1,475 @@
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::PagerDutyError;
+use crate::config::{create_private_dir, write_private};
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+/// Responses are deleted this long after they were saved. It's well past every TTL so
+/// offline mode still has something to show.
+const KEEP_FOR: u64 = 7 * DAY;
+/// Query parameters that move with the clock. Offline, a response for another window is
+/// better than none.
+const TIME_PARAMS: [&str; 2] = ["since", "until"];
+
+/// How the on-disk cache of PagerDuty responses is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Use responses younger than their TTL, fetch and save everything else.
+    Normal,
+    /// Always fetch, but save what was fetched for next time.
+    Refresh,
+    /// Neither read nor write the cache.
+    Disabled,
+    /// Only read the cache, however old, and never talk to PagerDuty.
+    Offline,
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheOptions {
+    pub mode: CacheMode,
+    /// Where responses are saved, nothing is cached without one.
+    pub dir: Option<PathBuf>,
+}
+
+impl std::default::Default for CacheOptions {
+    fn default() -> Self {
+        CacheOptions {
+            mode: CacheMode::Normal,
+            dir: None,
+        }
+    }
+}
+
+impl CacheOptions {
+    /// `$XDG_CACHE_HOME/pagerduty-cli`, falling back to `~/.cache/pagerduty-cli`.
+    pub fn default_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .map(|dir| dir.join("pagerduty-cli"))
+    }
+}
+
+/// How long a response for `path`, relative to the API URL, can be reused. `None` for
+/// anything that changes often enough that it should always be fetched.
+fn ttl_for(path: &str) -> Option<Duration> {
+    let segments: Vec<&str> = path.split('/').collect();
+    let seconds = match segments.as_slice() {
+        ["users"]
+        | ["users", _]
+        | ["users", _, "contact_methods"]
+        | ["teams"]
+        | ["teams", _, "members"]
+        | ["services"]
+        | ["abilities"] => DAY,
+        ["escalation_policies"] | ["schedules"] | ["schedules", _] => HOUR,
+        ["oncalls"] => 5 * MINUTE,
+        _ => return None,
+    };
+    Some(Duration::from_secs(seconds))
+}
+
+/// The current time rounded down to how long responses for `path` are cached, to use as
+/// `since` or `until`. Every run asks for the same window then, so they share a response.
+pub fn cacheable_now(path: &str) -> DateTime<Utc> {
+    let now = Utc::now();
+    let ttl = match ttl_for(path) {
+        Some(ttl) => ttl.as_secs() as i64,
+        None => return now,
+    };
+
+    now - chrono::Duration::seconds(now.timestamp().rem_euclid(ttl))
+        - chrono::Duration::nanoseconds(now.timestamp_subsec_nanos().into())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    fetched_at: u64,
+    body: String,
+}
+
+/// Saves GET responses to disk, keyed by token, endpoint and query. File names start with the
+/// account and the query without its time window, so both can be found without reading them.
+pub(crate) struct ResponseCache {
+    mode: CacheMode,
+    dir: Option<PathBuf>,
+    api_url: String,
+    auth_token: String,
+    /// Old responses are only looked for once per run.
+    pruned: AtomicBool,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(options: &CacheOptions, api_url: &str, auth_token: &str) -> Self {
+        ResponseCache {
+            mode: options.mode,
+            dir: options.dir.clone(),
+            api_url: api_url.to_string(),
+            auth_token: auth_token.to_string(),
+            pruned: AtomicBool::new(false),
+        }
+    }
+
+    /// A saved response that can be used instead of calling PagerDuty. In offline mode
+    /// anything that isn't saved is an error.
+    pub(crate) fn read(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+    ) -> Result<Option<String>, PagerDutyError> {
+        let entry = match self.mode {
+            CacheMode::Normal | CacheMode::Offline => self.load(url, query),
+            CacheMode::Refresh | CacheMode::Disabled => None,
+        };
+
+        match (self.mode, entry) {
+            (CacheMode::Offline, Some(entry)) => Ok(Some(entry.body)),
+            (CacheMode::Offline, None) if has_time_window(query) => {
+                match self.load_latest(url, query) {
+                    Some(entry) => {
+                        debug!("Using the latest cached window for {}", url);
+                        Ok(Some(entry.body))
+                    }
+                    None => Err(PagerDutyError::NotCached {
+                        url: url.to_string(),
+                    }),
+                }
+            }
+            (CacheMode::Offline, None) => Err(PagerDutyError::NotCached {
+                url: url.to_string(),
+            }),
+            (_, Some(entry)) => {
+                let ttl = self.ttl(url).unwrap_or_default();
+                if now().saturating_sub(entry.fetched_at) < ttl.as_secs() {
+                    debug!("Using cached response for {}", url);
+                    Ok(Some(entry.body))
+                } else {
+                    Ok(None)
+                }
+            }
+            (_, None) => Ok(None),
+        }
+    }
+
+    pub(crate) fn write(&self, url: &str, query: &[(&str, String)], body: &str) {
+        if self.mode == CacheMode::Disabled || self.ttl(url).is_none() {
+            return;
+        }
+        let path = match self.path(url, query) {
+            Some(path) => path,
+            None => return,
+        };
+        if !self.pruned.swap(true, Ordering::Relaxed) {
+            self.prune(Duration::from_secs(KEEP_FOR));
+        }
+
+        let entry = CacheEntry {
+            fetched_at: now(),
+            body: body.to_string(),
+        };
+        // Responses hold contact details and integration keys, so only the owner can read them.
+        let result = create_private_dir(path.parent().unwrap()).and_then(|_| {
+            write_private(
+                &path,
+                &serde_json::to_string(&entry).expect("entry to serialize"),
+            )
+        });
+        if let Err(e) = result {
+            warn!("Unable to cache response in {}: {}", path.display(), e);
+        }
+    }
+
+    /// Fails in offline mode, otherwise forgets everything saved for this account so a change
+    /// isn't hidden by an older response.
+    pub(crate) fn before_write(&self, url: &str) -> Result<(), PagerDutyError> {
+        match self.mode {
+            CacheMode::Offline => Err(PagerDutyError::NotCached {
+                url: url.to_string(),
+            }),
+            CacheMode::Disabled => Ok(()),
+            CacheMode::Normal | CacheMode::Refresh => {
+                self.clear();
+                Ok(())
+            }
+        }
+    }
+
+    fn clear(&self) {
+        let prefix = format!("{:016x}-", self.account_key());
+        self.remove_where(|entry| entry.file_name().to_string_lossy().starts_with(&prefix));
+    }
+
+    /// Delete responses saved more than `age` ago.
+    fn prune(&self, age: Duration) {
+        self.remove_where(|entry| {
+            entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                // Without a time it can't be told apart from an old one.
+                .map(|elapsed| elapsed >= age)
+                .unwrap_or(true)
+        });
+    }
+
+    fn remove_where<F>(&self, mut should_remove: F)
+    where
+        F: FnMut(&std::fs::DirEntry) -> bool,
+    {
+        let entries = match self.dir.as_ref().map(std::fs::read_dir) {
+            Some(Ok(entries)) => entries,
+            _ => return,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension() == Some(std::ffi::OsStr::new("json")) && should_remove(&entry) {
+                std::fs::remove_file(&path).ok();
+            }
+        }
+    }
+
+    fn load(&self, url: &str, query: &[(&str, String)]) -> Option<CacheEntry> {
+        let path = self.path(url, query)?;
+        let contents = std::fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!("Ignoring unreadable cache entry {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// The most recently saved response for `url` and `query`, whatever its time window.
+    fn load_latest(&self, url: &str, query: &[(&str, String)]) -> Option<CacheEntry> {
+        let prefix = format!(
+            "{:016x}-{:016x}-",
+            self.account_key(),
+            self.query_key(url, query, false)
+        );
+        let entries = std::fs::read_dir(self.dir.as_ref()?).ok()?;
+
+        entries
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+            .filter_map(|contents| serde_json::from_str::<CacheEntry>(&contents).ok())
+            .max_by_key(|entry| entry.fetched_at)
+    }
+
+    fn ttl(&self, url: &str) -> Option<Duration> {
+        let path = url.strip_prefix(&self.api_url)?.trim_start_matches('/');
+        ttl_for(path)
+    }
+
+    fn path(&self, url: &str, query: &[(&str, String)]) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!(
+            "{:016x}-{:016x}-{:016x}.json",
+            self.account_key(),
+            self.query_key(url, query, false),
+            self.query_key(url, query, true)
+        )))
+    }
+
+    fn account_key(&self) -> u64 {
+        fnv1a(&format!("{}\n{}", self.auth_token, self.api_url))
+    }
+
+    fn query_key(&self, url: &str, query: &[(&str, String)], with_time: bool) -> u64 {
+        let mut key = format!("{}\n{}", self.auth_token, url);
+        for (name, value) in query {
+            if with_time || !TIME_PARAMS.contains(name) {
+                key.push_str(&format!("\n{}={}", name, value));
+            }
+        }
+        fnv1a(&key)
+    }
+}
+
+fn has_time_window(query: &[(&str, String)]) -> bool {
+    query.iter().any(|(name, _)| TIME_PARAMS.contains(name))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// A hash that doesn't change between releases, unlike `DefaultHasher`, so upgrading
+/// doesn't throw the cache away.
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cache(mode: CacheMode, name: &str) -> ResponseCache {
+        let dir = std::env::temp_dir().join(format!("pd-cache-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+
+        ResponseCache::new(
+            &CacheOptions {
+                mode,
+                dir: Some(dir),
+            },
+            "https://api.pagerduty.com",
+            "token",
+        )
+    }
+
+    #[test]
+    fn only_slow_changing_resources_are_cached() {
+        assert_eq!(Some(Duration::from_secs(DAY)), ttl_for("users"));
+        assert_eq!(Some(Duration::from_secs(DAY)), ttl_for("users/me"));
+        assert_eq!(
+            Some(Duration::from_secs(HOUR)),
+            ttl_for("schedules/PI7DH85")
+        );
+        assert_eq!(None, ttl_for("schedules/PI7DH85/overrides"));
+        assert_eq!(None, ttl_for("incidents"));
+    }
+
+    #[test]
+    fn windows_starting_now_are_rounded_to_the_ttl() {
+        assert_eq!(0, cacheable_now("schedules").timestamp() % HOUR as i64);
+        assert_eq!(
+            0,
+            cacheable_now("oncalls").timestamp() % (5 * MINUTE) as i64
+        );
+        assert!(Utc::now() - cacheable_now("schedules") < chrono::Duration::hours(1));
+    }
+
+    #[test]
+    fn old_responses_are_pruned() {
+        let cache = cache(CacheMode::Normal, "prune");
+        let url = "https://api.pagerduty.com/users";
+        cache.write(url, &[], "users");
+
+        cache.prune(Duration::from_secs(KEEP_FOR));
+        assert!(cache.load(url, &[]).is_some());
+        cache.prune(Duration::from_secs(0));
+        assert!(cache.load(url, &[]).is_none());
+    }
+
+    #[test]
+    fn responses_are_keyed_by_query() {
+        let cache = cache(CacheMode::Normal, "query");
+        let url = "https://api.pagerduty.com/users";
+        let query = [("offset", "0".to_string())];
+
+        cache.write(url, &query, "first page");
+
+        assert_eq!(
+            Some("first page".to_string()),
+            cache.read(url, &query).unwrap()
+        );
+        assert_eq!(None, cache.read(url, &[("offset", "100".into())]).unwrap());
+        assert_eq!(
+            None,
+            cache
+                .read("https://api.pagerduty.com/incidents", &query)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn only_the_owner_can_read_responses() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let cache = cache(CacheMode::Normal, "private");
+        let url = "https://api.pagerduty.com/users";
+        cache.write(url, &[], "users");
+
+        let mode = |path: &PathBuf| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let path = cache.path(url, &[]).unwrap();
+        assert_eq!(0o600, mode(&path));
+        assert_eq!(0o700, mode(&path.parent().unwrap().to_path_buf()));
+    }
+
+    #[test]
+    fn refresh_writes_without_reading() {
+        let cache = cache(CacheMode::Refresh, "refresh");
+        let url = "https://api.pagerduty.com/services";
+
+        cache.write(url, &[], "services");
+
+        assert_eq!(None, cache.read(url, &[]).unwrap());
+        assert!(cache.load(url, &[]).is_some());
+    }
+
+    #[test]
+    fn offline_misses_are_errors() {
+        let cache = cache(CacheMode::Offline, "offline");
+        let url = "https://api.pagerduty.com/oncalls";
+
+        match cache.read(url, &[]) {
+            Err(PagerDutyError::NotCached { url: missing }) => assert_eq!(url, missing),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(cache.before_write(url).is_err());
+    }
+
+    #[test]
+    fn offline_falls_back_to_the_latest_window() {
+        let cache = cache(CacheMode::Normal, "window");
+        let url = "https://api.pagerduty.com/oncalls";
+        let window = |since: &str| [("since", since.to_string()), ("offset", "0".to_string())];
+        cache.write(url, &window("2020-01-01T00:00:00Z"), "oncalls");
+
+        let later = window("2020-01-01T00:05:00Z");
+        assert_eq!(None, cache.read(url, &later).unwrap());
+
+        let offline = ResponseCache {
+            mode: CacheMode::Offline,
+            ..cache
+        };
+        assert_eq!(
+            Some("oncalls".to_string()),
+            offline.read(url, &later).unwrap()
+        );
+        assert!(offline
+            .read(url, &[("since", "2020".into()), ("offset", "100".into())])
+            .is_err());
+    }
+
+    #[test]
+    fn writes_only_clear_their_own_account() {
+        let cache = cache(CacheMode::Normal, "accounts");
+        let other = ResponseCache::new(
+            &CacheOptions {
+                mode: CacheMode::Normal,
+                dir: cache.dir.clone(),
+            },
+            "https://api.eu.pagerduty.com",
+            "other-token",
+        );
+        cache.write("https://api.pagerduty.com/users", &[], "users");
+        other.write("https://api.eu.pagerduty.com/users", &[], "other users");
+
+        cache
+            .before_write("https://api.pagerduty.com/incidents")
+            .unwrap();
+
+        assert!(cache.load("https://api.pagerduty.com/users", &[]).is_none());
+        assert!(other
+            .load("https://api.eu.pagerduty.com/users", &[])
+            .is_some());
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt;
+
+const BODY_EXCERPT_LENGTH: usize = 200;
+
+/// Everything that can go wrong while talking to PagerDuty.
+///
+/// Each category maps to a distinct process exit code, so wrappers can tell
+/// "the token was revoked" apart from "nobody is on call".
+#[derive(Debug)]
+pub enum PagerDutyError {
+    /// PagerDuty rejected the token (HTTP 401 or 403).
+    AuthFailure { status: u16, body: String },
+    /// PagerDuty is throttling requests (HTTP 429).
+    RateLimited { retry_after: Option<u64> },
+    /// Any other non-success status.
+    HttpStatus { status: u16, body: String },
+    /// The response could not be understood.
+    Parse { message: String, body: String },
+    /// The request never completed.
+    Network(reqwest::Error),
+    /// A name given on the command line did not match anything in PagerDuty.
+    NotFound { kind: &'static str, name: String },
+    /// More than one object shares a name that has to be unique in the output.
+    Duplicates {
+        kind: &'static str,
+        names: Vec<String>,
+    },
+    /// A name or regex given on the command line matched several objects where one was needed.
+    Ambiguous {
+        kind: &'static str,
+        name: String,
+        matches: Vec<String>,
+    },
+    /// Running with `--offline` and the response isn't in the cache.
+    NotCached { url: String },
+    /// The config file couldn't be read or doesn't make sense.
+    Config { path: String, message: String },
+    /// The `token_command` of a profile failed or printed nothing.
+    TokenCommand { command: String, message: String },
+    /// Checked before changing anything, the token's user or account can't do it.
+    NotAllowed {
+        action: &'static str,
+        reason: String,
+    },
+    /// There are more records than PagerDuty pages through, and no cursor to get the rest.
+    TooManyRecords { url: String, limit: u32 },
+}
+
+impl PagerDutyError {
+    pub fn from_status(status: u16, body: &str, retry_after: Option<u64>) -> Self {
+        let body = excerpt(body);
+        match status {
+            401 | 403 => PagerDutyError::AuthFailure { status, body },
+            429 => PagerDutyError::RateLimited { retry_after },
+            _ => PagerDutyError::HttpStatus { status, body },
+        }
+    }
+
+    pub fn parse(error: serde_json::Error, body: &str) -> Self {
+        PagerDutyError::Parse {
+            message: error.to_string(),
+            body: excerpt(body),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PagerDutyError::AuthFailure { .. } => 3,
+            PagerDutyError::RateLimited { .. } => 4,
+            PagerDutyError::HttpStatus { .. } => 5,
+            PagerDutyError::Parse { .. } => 6,
+            PagerDutyError::Network(_) => 7,
+            PagerDutyError::NotFound { .. } => 8,
+            PagerDutyError::Duplicates { .. } | PagerDutyError::Ambiguous { .. } => 9,
+            PagerDutyError::NotCached { .. } => 10,
+            PagerDutyError::Config { .. } => 11,
+            PagerDutyError::TokenCommand { .. } => 12,
+            PagerDutyError::NotAllowed { .. } => 13,
+            PagerDutyError::TooManyRecords { .. } => 14,
+        }
+    }
+}
+
+fn excerpt(body: &str) -> String {
+    if body.chars().count() > BODY_EXCERPT_LENGTH {
+        let mut short: String = body.chars().take(BODY_EXCERPT_LENGTH).collect();
+        short.push_str("...");
+        short
+    } else {
+        body.to_string()
+    }
+}
+
+impl fmt::Display for PagerDutyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PagerDutyError::AuthFailure { status, body } => write!(
+                f,
+                "PagerDuty rejected the API token (HTTP {}): {}",
+                status, body
+            ),
+            PagerDutyError::RateLimited {
+                retry_after: Some(seconds),
+            } => write!(
+                f,
+                "PagerDuty rate limit reached, retry after {} seconds",
+                seconds
+            ),
+            PagerDutyError::RateLimited { retry_after: None } => {
+                write!(f, "PagerDuty rate limit reached")
+            }
+            PagerDutyError::HttpStatus { status, body } => {
+                write!(f, "PagerDuty returned HTTP {}: {}", status, body)
+            }
+            PagerDutyError::Parse { message, body } => write!(
+                f,
+                "Unable to parse output from PagerDuty: {}. Body: {}",
+                message, body
+            ),
+            PagerDutyError::Network(e) => write!(f, "Request to PagerDuty failed: {}", e),
+            PagerDutyError::NotFound { kind, name } => {
+                write!(f, "Unable to find {} `{}` in PagerDuty", kind, name)
+            }
+            PagerDutyError::Config { path, message } => {
+                write!(f, "Invalid config file {}: {}", path, message)
+            }
+            PagerDutyError::TokenCommand { command, message } => {
+                write!(f, "Unable to get a token from `{}`: {}", command, message)
+            }
+            PagerDutyError::NotAllowed { action, reason } => {
+                write!(f, "Unable to {} with this token, {}", action, reason)
+            }
+            PagerDutyError::TooManyRecords { url, limit } => write!(
+                f,
+                "{} has more than {} records, which is as far as PagerDuty pages without a cursor",
+                url, limit
+            ),
+            PagerDutyError::Ambiguous {
+                kind,
+                name,
+                matches,
+            } => write!(
+                f,
+                "`{}` matches more than one {}: {}",
+                name,
+                kind,
+                matches
+                    .iter()
+                    .map(|name| format!("`{}`", name))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            PagerDutyError::NotCached { url } => write!(
+                f,
+                "No cached response for {}, run without --offline to fetch it",
+                url
+            ),
+            PagerDutyError::Duplicates { kind, names } => write!(
+                f,
+                "More than one {} is named {}",
+                kind,
+                names
+                    .iter()
+                    .map(|name| format!("`{}`", name))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PagerDutyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PagerDutyError::Network(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for PagerDutyError {
+    fn from(e: reqwest::Error) -> Self {
+        PagerDutyError::Network(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn status_maps_to_category() {
+        assert_eq!(3, PagerDutyError::from_status(401, "", None).exit_code());
+        assert_eq!(3, PagerDutyError::from_status(403, "", None).exit_code());
+        assert_eq!(4, PagerDutyError::from_status(429, "", None).exit_code());
+        assert_eq!(5, PagerDutyError::from_status(500, "", None).exit_code());
+    }
+
+    #[test]
+    fn long_bodies_are_shortened() {
+        let body = "a".repeat(1000);
+        match PagerDutyError::from_status(500, &body, None) {
+            PagerDutyError::HttpStatus { body, .. } => {
+                assert_eq!(BODY_EXCERPT_LENGTH + 3, body.len())
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+}
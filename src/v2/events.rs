@@ -0,0 +1,244 @@
+use log::{debug, warn};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::retry::{backoff_delay, is_retryable, retry_after_seconds, RequestOptions};
+use super::PagerDutyError;
+
+/// Alerts sent through the Events API use an integration's routing key, not a REST API token.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertEvent {
+    pub routing_key: String,
+    pub event_action: EventAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup_key: Option<String>,
+    /// Only sent when triggering, PagerDuty ignores it otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<AlertPayload>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<EventLink>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<EventImage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventAction {
+    Trigger,
+    Acknowledge,
+    Resolve,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertPayload {
+    pub summary: String,
+    pub source: String,
+    pub severity: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_details: Option<serde_json::Value>,
+}
+
+/// Records that something changed, like a deploy, without paging anyone.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeEvent {
+    pub routing_key: String,
+    pub payload: ChangePayload,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<EventLink>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangePayload {
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_details: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EventLink {
+    pub href: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EventImage {
+    pub src: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventResponse {
+    pub status: String,
+    pub message: String,
+    /// Only returned for alert events, use it to acknowledge or resolve the alert later.
+    #[serde(default)]
+    pub dedup_key: Option<String>,
+}
+
+pub struct EventsClient {
+    client: Client,
+    events_url: String,
+    max_attempts: u32,
+}
+
+impl EventsClient {
+    pub fn new(events_url: &str, options: RequestOptions) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Should be able to make client");
+
+        EventsClient {
+            client,
+            events_url: events_url.trim_end_matches('/').to_string(),
+            max_attempts: options.max_attempts.max(1),
+        }
+    }
+
+    pub async fn send_event(&self, event: &AlertEvent) -> Result<EventResponse, PagerDutyError> {
+        // PagerDuty folds events with the same dedup key into one alert, so only then is
+        // sending it again safe.
+        self.post("v2/enqueue", event, event.dedup_key.is_some())
+            .await
+    }
+
+    pub async fn send_change_event(
+        &self,
+        event: &ChangeEvent,
+    ) -> Result<EventResponse, PagerDutyError> {
+        self.post("v2/change/enqueue", event, false).await
+    }
+
+    async fn post<B, T>(&self, path: &str, body: &B, idempotent: bool) -> Result<T, PagerDutyError>
+    where
+        B: Serialize,
+        T: DeserializeOwned,
+    {
+        let url = format!("{}/{}", self.events_url, path);
+
+        let mut attempt = 1;
+        let text_body = loop {
+            match self.send(&url, body).await {
+                Ok(text_body) => break text_body,
+                Err(e) if attempt < self.max_attempts && is_retryable(&e, idempotent) => {
+                    let delay = backoff_delay(attempt, &e);
+                    warn!(
+                        "{} (attempt {}/{}). Retrying in {:?}",
+                        e, attempt, self.max_attempts, delay
+                    );
+                    tokio::time::delay_for(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        serde_json::from_str(&text_body).map_err(|e| PagerDutyError::parse(e, &text_body))
+    }
+
+    async fn send<B>(&self, url: &str, body: &B) -> Result<String, PagerDutyError>
+    where
+        B: Serialize,
+    {
+        let resp = self.client.post(url).json(body).send().await?;
+
+        let status = resp.status();
+        let retry_after = retry_after_seconds(&resp);
+        let text_body = resp.text().await?;
+
+        if !status.is_success() {
+            debug!("PagerDuty returned {} for {}", status, url);
+            return Err(PagerDutyError::from_status(
+                status.as_u16(),
+                &text_body,
+                retry_after,
+            ));
+        }
+
+        Ok(text_body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resolve_only_sends_the_dedup_key() {
+        let event = AlertEvent {
+            routing_key: "R0UT1NGK3Y".into(),
+            event_action: EventAction::Resolve,
+            dedup_key: Some("disk-full".into()),
+            payload: None,
+            links: vec![],
+            images: vec![],
+        };
+
+        assert_eq!(
+            json!({
+                "routing_key": "R0UT1NGK3Y",
+                "event_action": "resolve",
+                "dedup_key": "disk-full"
+            }),
+            serde_json::to_value(&event).unwrap()
+        );
+    }
+
+    #[test]
+    fn trigger_sends_the_payload() {
+        let event = AlertEvent {
+            routing_key: "R0UT1NGK3Y".into(),
+            event_action: EventAction::Trigger,
+            dedup_key: None,
+            payload: Some(AlertPayload {
+                summary: "Disk is full".into(),
+                source: "db-1".into(),
+                severity: "critical".into(),
+                timestamp: None,
+                component: Some("postgres".into()),
+                group: None,
+                class: None,
+                custom_details: Some(json!({ "free": "0b" })),
+            }),
+            links: vec![EventLink {
+                href: "https://example.com".into(),
+                text: None,
+            }],
+            images: vec![],
+        };
+
+        assert_eq!(
+            json!({
+                "routing_key": "R0UT1NGK3Y",
+                "event_action": "trigger",
+                "payload": {
+                    "summary": "Disk is full",
+                    "source": "db-1",
+                    "severity": "critical",
+                    "component": "postgres",
+                    "custom_details": { "free": "0b" }
+                },
+                "links": [{ "href": "https://example.com" }]
+            }),
+            serde_json::to_value(&event).unwrap()
+        );
+    }
+}
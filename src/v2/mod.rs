@@ -1,11 +1,26 @@
 extern crate log;
 
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::duration::format_duration;
 
 mod api;
+mod cache;
+mod error;
+mod events;
+mod retry;
 
 use api::*;
+pub use cache::{cacheable_now, CacheMode, CacheOptions};
+pub use error::PagerDutyError;
+pub use events::{
+    AlertEvent, AlertPayload, ChangeEvent, ChangePayload, EventAction, EventImage, EventLink,
+    EventsClient,
+};
+pub use retry::RequestOptions;
 
 #[derive(Debug, Deserialize, Clone, Eq, Ord, PartialEq, PartialOrd)]
 pub struct PagerDutyUserGroups {
@@ -20,11 +35,29 @@ pub struct EscalationPolicy {
     pub policy_name: String,
     pub oncall_groups: Vec<PagerDutyUserGroups>,
     pub services: Vec<String>,
+    /// How many times the rules repeat when nobody acknowledges.
+    pub num_loops: u32,
+    /// IDs of the teams the policy belongs to.
+    pub teams: Vec<String>,
+    pub rules: Vec<EscalationRule>,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct EscalationRule {
+    pub escalation_delay_in_minutes: u32,
+    pub targets: Vec<EscalationTarget>,
+}
+
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+pub struct EscalationTarget {
+    pub id: String,
+    /// Either `user_reference` or `schedule_reference`.
+    pub target_type: String,
 }
 
 impl EscalationPolicy {
     #[cfg(test)]
-    fn new(id: &str, policy_name: &str) -> Self {
+    pub(crate) fn new(id: &str, policy_name: &str) -> Self {
         let formatted_name = format!("oncall-{}", policy_name);
         EscalationPolicy {
             id: id.to_string(),
@@ -35,10 +68,17 @@ impl EscalationPolicy {
                     id: formatted_name.clone(),
                     name: formatted_name.clone(),
                     email: formatted_name,
+                    shift_start: None,
+                    shift_end: None,
+                    schedule: None,
+                    teams: vec![],
                 }],
                 depth: 1,
             }],
             services: vec![],
+            num_loops: 0,
+            teams: vec![],
+            rules: vec![],
         }
     }
 }
@@ -68,12 +108,547 @@ pub struct PagerDutyUser {
     pub id: String,
     pub name: String,
     pub email: String,
+    /// When this user's current on call shift started, `None` when always on call.
+    pub shift_start: Option<String>,
+    /// When this user's current on call shift ends, `None` when always on call.
+    pub shift_end: Option<String>,
+    /// Name of the schedule that put the user on call, `None` when targeted directly.
+    pub schedule: Option<String>,
+    /// IDs of the teams the user belongs to.
+    #[serde(default)]
+    pub teams: Vec<String>,
 }
 
 impl PagerDutyUser {
     pub fn to_display(&self) -> String {
         format!("{} ({})", self.name, self.email)
     }
+
+    /// Describe why and until when the user is on call, like `via Primary, ends in 3h`.
+    pub fn shift_display(&self, now: DateTime<Utc>) -> String {
+        let source = match &self.schedule {
+            Some(schedule) => format!("via {}", schedule),
+            None => "directly".to_string(),
+        };
+
+        let end = self
+            .shift_end
+            .as_ref()
+            .and_then(|end| DateTime::parse_from_rfc3339(end).ok());
+
+        match end {
+            Some(end) if end > now => format!(
+                "{}, ends in {}",
+                source,
+                format_duration(end.with_timezone(&Utc) - now)
+            ),
+            Some(_) => format!("{}, ended", source),
+            None => source,
+        }
+    }
+}
+
+#[test]
+fn shift_display_describes_source_and_end() {
+    let now = DateTime::parse_from_rfc3339("2020-04-01T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let mut user = EscalationPolicy::new("abc123", "policy-1").oncall_groups[0].users[0].clone();
+
+    assert_eq!("directly", user.shift_display(now));
+
+    user.schedule = Some("Primary".into());
+    user.shift_end = Some("2020-04-01T15:30:00Z".into());
+    assert_eq!("via Primary, ends in 3h 30m", user.shift_display(now));
+
+    user.shift_end = Some("2020-04-01T11:00:00Z".into());
+    assert_eq!("via Primary, ended", user.shift_display(now));
+}
+
+/// A window of time to look for on call shifts in, as ISO 8601 timestamps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OncallWindow {
+    pub since: String,
+    pub until: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incident {
+    pub id: String,
+    pub incident_number: u64,
+    pub title: String,
+    pub status: String,
+    pub urgency: String,
+    pub created_at: String,
+    pub html_url: String,
+    pub service_id: String,
+    pub service_name: String,
+    pub escalation_policy_name: String,
+    pub assignees: Vec<String>,
+}
+
+/// Server side filters for listing incidents. Empty values are not sent.
+#[derive(Debug, Clone, Default)]
+pub struct IncidentQuery {
+    pub statuses: Vec<String>,
+    pub urgencies: Vec<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub team_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    pub time_zone: String,
+    pub description: Option<String>,
+    pub html_url: String,
+    /// IDs of the teams the schedule belongs to.
+    pub teams: Vec<String>,
+    pub layers: Vec<ScheduleLayer>,
+    /// Who is actually on call once every layer and override is applied.
+    pub final_schedule: Vec<ScheduleEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleLayer {
+    pub id: String,
+    pub name: String,
+    pub start: String,
+    pub end: Option<String>,
+    /// When the rotation is considered to have started, which sets who is on call when.
+    pub rotation_virtual_start: Option<String>,
+    pub rotation_turn_length_seconds: i64,
+    /// Users in the rotation, in the order they take turns.
+    pub users: Vec<String>,
+    /// IDs of `users`, in the same order.
+    pub user_ids: Vec<String>,
+    pub restrictions: Vec<LayerRestriction>,
+    pub entries: Vec<ScheduleEntry>,
+}
+
+/// Limits a layer to part of the day or week.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerRestriction {
+    /// Either `daily_restriction` or `weekly_restriction`.
+    pub restriction_type: String,
+    pub start_time_of_day: String,
+    pub duration_seconds: u64,
+    /// 1 for Monday through 7 for Sunday, only set on weekly restrictions.
+    pub start_day_of_week: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleEntry {
+    pub start: String,
+    pub end: String,
+    pub user_id: String,
+    pub user_name: String,
+}
+
+/// Someone covering part of a schedule in place of whoever the layers put on call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleOverride {
+    pub id: String,
+    pub start: String,
+    pub end: String,
+    pub user_id: String,
+    pub user_name: String,
+}
+
+impl Schedule {
+    /// The final schedule as it would look once `user` covers `start` to `end`.
+    ///
+    /// Entries the override overlaps are trimmed, or dropped when it covers them completely.
+    pub fn preview_override(
+        &self,
+        start: &str,
+        end: &str,
+        user: &PagerDutyUser,
+    ) -> Vec<ScheduleEntry> {
+        let parse = |time: &str| DateTime::parse_from_rfc3339(time).ok();
+        let (override_start, override_end) = (parse(start), parse(end));
+
+        let mut entries = Vec::new();
+        for entry in &self.final_schedule {
+            let overlaps = match (
+                parse(&entry.start),
+                parse(&entry.end),
+                override_start,
+                override_end,
+            ) {
+                (Some(entry_start), Some(entry_end), Some(start), Some(end)) => {
+                    entry_start < end && entry_end > start
+                }
+                _ => false,
+            };
+
+            if !overlaps {
+                entries.push(entry.clone());
+                continue;
+            }
+
+            if parse(&entry.start) < override_start {
+                entries.push(ScheduleEntry {
+                    end: start.to_string(),
+                    ..entry.clone()
+                });
+            }
+            if parse(&entry.end) > override_end {
+                entries.push(ScheduleEntry {
+                    start: end.to_string(),
+                    ..entry.clone()
+                });
+            }
+        }
+
+        entries.push(ScheduleEntry {
+            start: start.to_string(),
+            end: end.to_string(),
+            user_id: user.id.clone(),
+            user_name: user.name.clone(),
+        });
+        entries.sort_by_key(|entry| parse(&entry.start));
+
+        entries
+    }
+}
+
+#[test]
+fn preview_override_trims_overlapping_entries() {
+    let entry = |start: &str, end: &str, name: &str| ScheduleEntry {
+        start: start.into(),
+        end: end.into(),
+        user_id: name.to_lowercase(),
+        user_name: name.into(),
+    };
+
+    let schedule = Schedule {
+        id: "PI7DH85".into(),
+        name: "Payments Primary".into(),
+        time_zone: "UTC".into(),
+        description: None,
+        html_url: "".into(),
+        teams: vec![],
+        layers: vec![],
+        final_schedule: vec![
+            entry("2020-04-01T00:00:00Z", "2020-04-02T00:00:00Z", "Ada"),
+            entry("2020-04-02T00:00:00Z", "2020-04-03T00:00:00Z", "Grace"),
+            entry("2020-04-03T00:00:00Z", "2020-04-04T00:00:00Z", "Linus"),
+        ],
+    };
+    let user = PagerDutyUser {
+        id: "margaret".into(),
+        name: "Margaret".into(),
+        email: "margaret@example.com".into(),
+        shift_start: None,
+        shift_end: None,
+        schedule: None,
+        teams: vec![],
+    };
+
+    let preview = schedule.preview_override("2020-04-01T12:00:00Z", "2020-04-03T00:00:00Z", &user);
+    assert_eq!(
+        vec![
+            entry("2020-04-01T00:00:00Z", "2020-04-01T12:00:00Z", "Ada"),
+            entry("2020-04-01T12:00:00Z", "2020-04-03T00:00:00Z", "Margaret"),
+            entry("2020-04-03T00:00:00Z", "2020-04-04T00:00:00Z", "Linus"),
+        ],
+        preview
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Service {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub escalation_policy_id: String,
+    /// Seconds before an acknowledged incident triggers again, `None` when disabled.
+    pub acknowledgement_timeout: Option<u64>,
+    /// Seconds before an incident resolves itself, `None` when disabled.
+    pub auto_resolve_timeout: Option<u64>,
+    pub alert_creation: Option<String>,
+    /// IDs of the teams the service belongs to.
+    pub teams: Vec<String>,
+    /// `active`, `warning`, `critical`, `maintenance` or `disabled`.
+    pub status: String,
+    pub escalation_policy_name: String,
+    pub urgency: UrgencyRule,
+    /// `None` when every alert opens its own incident.
+    pub alert_grouping: Option<AlertGrouping>,
+    pub integrations: Vec<Integration>,
+}
+
+impl Service {
+    #[cfg(test)]
+    pub(crate) fn new(id: &str, name: &str) -> Self {
+        Service {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            escalation_policy_id: "P9OFD2O".into(),
+            acknowledgement_timeout: None,
+            auto_resolve_timeout: None,
+            alert_creation: None,
+            teams: vec![],
+            status: "active".into(),
+            escalation_policy_name: "Default".into(),
+            urgency: UrgencyRule::Constant("high".into()),
+            alert_grouping: None,
+            integrations: vec![],
+        }
+    }
+}
+
+/// The urgency given to a service's incidents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrgencyRule {
+    Constant(String),
+    SupportHours { during: String, outside: String },
+}
+
+impl UrgencyRule {
+    pub fn to_display(&self) -> String {
+        match self {
+            UrgencyRule::Constant(urgency) => urgency.clone(),
+            UrgencyRule::SupportHours { during, outside } => {
+                format!("{} during support hours, {} outside", during, outside)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlertGrouping {
+    /// `time`, `intelligent` or `content_based`.
+    pub kind: String,
+    /// Minutes to keep grouping for, 0 to group until the incident resolves.
+    pub timeout: Option<u64>,
+}
+
+impl AlertGrouping {
+    pub fn to_display(&self) -> String {
+        match self.timeout {
+            Some(0) => format!("{}, until resolved", self.kind),
+            Some(minutes) => format!(
+                "{}, {}",
+                self.kind,
+                format_duration(chrono::Duration::minutes(minutes as i64))
+            ),
+            None => self.kind.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Integration {
+    pub id: String,
+    pub name: String,
+    pub vendor: Option<String>,
+    /// The routing key alerts are sent with, only there for events integrations.
+    pub key: Option<String>,
+}
+
+/// A PagerDuty user as configured on the account, rather than who is on call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub role: Option<String>,
+    pub time_zone: Option<String>,
+    pub job_title: Option<String>,
+    pub description: Option<String>,
+    /// IDs of the teams the user belongs to.
+    pub teams: Vec<String>,
+    /// Only has addresses when fetched with `include[]=contact_methods`.
+    pub contact_methods: Vec<ContactMethod>,
+    /// Empty unless fetched with `include[]=notification_rules`.
+    pub notification_rules: Vec<NotificationRule>,
+}
+
+impl User {
+    /// Why the user might not hear about an incident: no phone to call, or nothing set up to
+    /// notify them of high urgency incidents.
+    pub fn readiness_problems(&self) -> Vec<&'static str> {
+        let mut problems = Vec::new();
+        if !self
+            .contact_methods
+            .iter()
+            .any(|method| method.kind == "phone")
+        {
+            problems.push("no phone contact method");
+        }
+        if !self
+            .notification_rules
+            .iter()
+            .any(|rule| rule.urgency == "high")
+        {
+            problems.push("no high-urgency notification rule");
+        }
+        problems
+    }
+}
+
+/// How and when a user is notified once an incident is assigned to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationRule {
+    /// `high` or `low`.
+    pub urgency: String,
+    pub start_delay_in_minutes: u32,
+    pub contact_method: ContactMethod,
+}
+
+impl NotificationRule {
+    pub fn to_display(&self) -> String {
+        format!(
+            "after {}m - {} - {}",
+            self.start_delay_in_minutes, self.contact_method.kind, self.contact_method.label
+        )
+    }
+}
+
+/// One way of reaching a user, like a phone number or the PagerDuty app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactMethod {
+    /// `email`, `phone`, `sms` or `push`.
+    pub kind: String,
+    pub label: String,
+    /// Phone numbers include their country code, push methods have no address.
+    pub address: Option<String>,
+}
+
+impl ContactMethod {
+    pub fn to_display(&self) -> String {
+        match &self.address {
+            Some(address) => format!("{} - {} - {}", self.kind, self.label, address),
+            None => format!("{} - {}", self.kind, self.label),
+        }
+    }
+}
+
+/// Roles that can see everything but change nothing.
+const READ_ONLY_ROLES: [&str; 3] = ["observer", "read_only_user", "read_only_limited_user"];
+
+/// The token being used and what it can do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// Who the token belongs to, `None` for an account token.
+    pub user: Option<User>,
+    /// Like `acme` for `acme.pagerduty.com`.
+    pub subdomain: Option<String>,
+    /// Features of the account, like `teams` or `manage_schedules`.
+    pub abilities: Vec<String>,
+}
+
+impl TokenInfo {
+    /// Whether the token's user can only look. Account tokens can't tell.
+    pub fn is_read_only(&self) -> bool {
+        self.user
+            .as_ref()
+            .and_then(|user| user.role.as_deref())
+            .map(|role| READ_ONLY_ROLES.contains(&role))
+            .unwrap_or(false)
+    }
+
+    /// Why the token can't be used to make changes that need `ability`, if it can't.
+    pub fn deny_reason(&self, ability: Option<&str>) -> Option<String> {
+        if self.is_read_only() {
+            let user = self.user.as_ref().unwrap();
+            return Some(format!(
+                "{} has the {} role",
+                user.name,
+                user.role.as_deref().unwrap_or_default()
+            ));
+        }
+        match ability {
+            Some(ability) if !self.abilities.iter().any(|a| a == ability) => Some(format!(
+                "the account doesn't have the `{}` ability",
+                ability
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Team {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Someone on a team and what they can do there: `manager`, `responder` or `observer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamMember {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+}
+
+/// A team with its members and what it owns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamDetails {
+    pub team: Team,
+    pub members: Vec<TeamMember>,
+    /// Names of the escalation policies the team owns.
+    pub escalation_policies: Vec<String>,
+    /// Names of the services the team owns.
+    pub services: Vec<String>,
+}
+
+/// Everything configured on an account, as needed to export it.
+#[derive(Debug, Clone, Default)]
+pub struct Account {
+    pub escalation_policies: Vec<EscalationPolicy>,
+    pub services: Vec<Service>,
+    pub schedules: Vec<Schedule>,
+    pub teams: Vec<Team>,
+    pub users: Vec<User>,
+}
+
+/// A period where a set of services won't create incidents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    pub id: String,
+    pub sequence_number: u64,
+    pub start_time: String,
+    pub end_time: String,
+    pub description: Option<String>,
+    pub html_url: String,
+    /// Names of the services in maintenance.
+    pub services: Vec<String>,
+    /// IDs of the teams the window belongs to.
+    pub teams: Vec<String>,
+}
+
+/// Which maintenance windows to list, relative to now. `All` sends no filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenanceFilter {
+    All,
+    Ongoing,
+    Future,
+}
+
+/// A change to apply to one or more incidents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncidentAction {
+    Acknowledge,
+    Resolve,
+    ReassignToUser(String),
+    ReassignToPolicy(String),
+    /// Snooze for this many seconds.
+    Snooze(u64),
+    /// Merge the incident into the incident with this ID.
+    MergeInto(String),
+}
+
+#[derive(Debug)]
+pub struct IncidentActionResult {
+    pub incident_id: String,
+    pub result: Result<(), PagerDutyError>,
 }
 
 pub struct PagerDutyClient {
@@ -81,14 +656,216 @@ pub struct PagerDutyClient {
 }
 
 impl PagerDutyClient {
-    pub fn new(auth_token: &str) -> Self {
-        let api = PagerDutyApi::new(auth_token.into());
+    pub fn new(auth_token: &str, api_url: &str, options: RequestOptions) -> Self {
+        let api = PagerDutyApi::new(auth_token.into(), api_url.into(), options);
 
         PagerDutyClient { api }
     }
 
-    pub async fn fetch_policies_for_account(&self) -> Vec<EscalationPolicy> {
-        self.api.get_escalation_policies().await
+    /// Fetch every policy with who is on call now, or every shift within `window` when given.
+    pub async fn fetch_policies_for_account(
+        &self,
+        window: Option<&OncallWindow>,
+    ) -> Result<Vec<EscalationPolicy>, PagerDutyError> {
+        self.api.get_escalation_policies(window).await
+    }
+
+    /// Like `fetch_policies_for_account`, along with every service so they're only fetched once.
+    pub async fn fetch_policies_and_services(
+        &self,
+    ) -> Result<(Vec<EscalationPolicy>, Vec<Service>), PagerDutyError> {
+        self.api.get_escalation_policies_and_services(None).await
+    }
+
+    pub async fn fetch_incidents(
+        &self,
+        query: &IncidentQuery,
+    ) -> Result<Vec<Incident>, PagerDutyError> {
+        self.api.get_incidents(query).await
+    }
+
+    pub async fn fetch_schedules<F>(
+        &self,
+        since: &str,
+        until: &str,
+        filter: F,
+    ) -> Result<Vec<Schedule>, PagerDutyError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.api.get_schedules(since, until, filter).await
+    }
+
+    pub async fn update_incidents(
+        &self,
+        from: &str,
+        incident_ids: &[String],
+        action: &IncidentAction,
+    ) -> Vec<IncidentActionResult> {
+        self.api.update_incidents(from, incident_ids, action).await
+    }
+
+    /// Find a user by ID, email or `me` for the owner of the API token.
+    pub async fn find_user(&self, user: &str) -> Result<PagerDutyUser, PagerDutyError> {
+        self.api.find_user(user).await
+    }
+
+    /// Check PagerDuty accepts the token, returning who it belongs to. Account tokens have no
+    /// user, so for them `/abilities` is tried instead and `None` returned.
+    pub async fn validate_token(&self) -> Result<Option<PagerDutyUser>, PagerDutyError> {
+        match self.api.find_user("me").await {
+            Ok(user) => Ok(Some(user)),
+            Err(PagerDutyError::HttpStatus { status: 400, .. }) => {
+                self.api.get_abilities().await.map(|_| None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Who owns the token, which account it's for and the account's abilities.
+    pub async fn fetch_token_info(&self) -> Result<TokenInfo, PagerDutyError> {
+        self.api.get_token_info().await
+    }
+
+    /// Fail before changing anything when the token can't be used to `action`, because its
+    /// user is read only or the account is missing `ability`.
+    pub async fn check_allowed(
+        &self,
+        action: &'static str,
+        ability: Option<&str>,
+    ) -> Result<(), PagerDutyError> {
+        match self.fetch_token_info().await?.deny_reason(ability) {
+            Some(reason) => Err(PagerDutyError::NotAllowed { action, reason }),
+            None => Ok(()),
+        }
+    }
+
+    /// Find a schedule by ID or name, rendered between `since` and `until`.
+    pub async fn find_schedule(
+        &self,
+        schedule: &str,
+        since: &str,
+        until: &str,
+    ) -> Result<Schedule, PagerDutyError> {
+        self.api.find_schedule(schedule, since, until).await
+    }
+
+    pub async fn fetch_overrides(
+        &self,
+        schedule_id: &str,
+        since: &str,
+        until: &str,
+    ) -> Result<Vec<ScheduleOverride>, PagerDutyError> {
+        self.api.get_overrides(schedule_id, since, until).await
+    }
+
+    pub async fn create_override(
+        &self,
+        schedule_id: &str,
+        start: &str,
+        end: &str,
+        user: &PagerDutyUser,
+    ) -> Result<ScheduleOverride, PagerDutyError> {
+        self.api
+            .create_override(schedule_id, start, end, user)
+            .await
+    }
+
+    pub async fn delete_override(
+        &self,
+        schedule_id: &str,
+        override_id: &str,
+    ) -> Result<(), PagerDutyError> {
+        self.api.delete_override(schedule_id, override_id).await
+    }
+
+    /// Fetch every policy, service, schedule, team and user on the account.
+    pub async fn fetch_account(&self) -> Result<Account, PagerDutyError> {
+        self.api.get_account().await
+    }
+
+    /// IDs of `teams`, each given as an ID or a case insensitive name.
+    pub async fn find_team_ids(&self, teams: &[&str]) -> Result<Vec<String>, PagerDutyError> {
+        self.api.find_team_ids(teams).await
+    }
+
+    /// Fetch every team that passes `filter`, with its members and what it owns.
+    pub async fn fetch_team_details<F>(&self, filter: F) -> Result<Vec<TeamDetails>, PagerDutyError>
+    where
+        F: Fn(&Team) -> bool,
+    {
+        self.api.get_team_details(filter).await
+    }
+
+    /// Fetch every service whose name passes `filter`.
+    pub async fn fetch_services<F>(&self, filter: F) -> Result<Vec<Service>, PagerDutyError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.api.get_services(filter).await
+    }
+
+    pub async fn fetch_maintenance_windows(
+        &self,
+        filter: MaintenanceFilter,
+    ) -> Result<Vec<MaintenanceWindow>, PagerDutyError> {
+        self.api.get_maintenance_windows(filter).await
+    }
+
+    /// Put `services` into maintenance from `start` to `end` on behalf of `from`.
+    pub async fn create_maintenance_window(
+        &self,
+        from: &str,
+        services: &[Service],
+        start: &str,
+        end: &str,
+        description: Option<&str>,
+    ) -> Result<MaintenanceWindow, PagerDutyError> {
+        self.api
+            .create_maintenance_window(from, services, start, end, description)
+            .await
+    }
+
+    /// End an ongoing maintenance window, or delete one that hasn't started yet.
+    pub async fn end_maintenance_window(&self, window_id: &str) -> Result<(), PagerDutyError> {
+        self.api.end_maintenance_window(window_id).await
+    }
+
+    pub async fn fetch_oncalls_for_user(
+        &self,
+        user: &PagerDutyUser,
+        window: Option<&OncallWindow>,
+    ) -> Result<Vec<EscalationPolicy>, PagerDutyError> {
+        self.api.get_oncalls_for_user(user, window).await
+    }
+
+    /// Fetch every user that passes `filter`, with their contact methods and notification rules.
+    pub async fn fetch_users<F>(&self, filter: F) -> Result<Vec<User>, PagerDutyError>
+    where
+        F: Fn(&User) -> bool,
+    {
+        self.api.get_users(filter).await
+    }
+
+    /// Fetch the contact methods of each user, keyed by user ID.
+    pub async fn fetch_contact_methods(
+        &self,
+        user_ids: &[String],
+    ) -> Result<BTreeMap<String, Vec<ContactMethod>>, PagerDutyError> {
+        self.api.get_contact_methods(user_ids).await
+    }
+
+    /// Open an incident on `service` on behalf of `from`.
+    pub async fn create_incident(
+        &self,
+        from: &str,
+        service: &Service,
+        title: &str,
+        urgency: &str,
+    ) -> Result<Incident, PagerDutyError> {
+        self.api
+            .create_incident(from, service, title, urgency)
+            .await
     }
 }
 
@@ -107,3 +884,37 @@ fn order_will_be_alpha() {
     assert_eq!("Connect", vec[0].policy_name);
     assert_eq!("Go", vec[1].policy_name);
 }
+
+#[test]
+fn read_only_users_and_missing_abilities_are_denied() {
+    let mut info = TokenInfo {
+        user: Some(User {
+            id: "PXPGF42".into(),
+            name: "Earline Greenholt".into(),
+            email: "earline@example.com".into(),
+            role: Some("limited_user".into()),
+            time_zone: None,
+            job_title: None,
+            description: None,
+            teams: vec![],
+            contact_methods: vec![],
+            notification_rules: vec![],
+        }),
+        subdomain: Some("acme".into()),
+        abilities: vec!["teams".into(), "manage_schedules".into()],
+    };
+    assert_eq!(None, info.deny_reason(Some("manage_schedules")));
+    assert_eq!(
+        Some("the account doesn't have the `sso` ability".to_string()),
+        info.deny_reason(Some("sso"))
+    );
+
+    info.user.as_mut().unwrap().role = Some("observer".into());
+    assert_eq!(
+        Some("Earline Greenholt has the observer role".to_string()),
+        info.deny_reason(None)
+    );
+
+    info.user = None;
+    assert_eq!(None, info.deny_reason(None));
+}
@@ -0,0 +1,146 @@
+use rand::Rng;
+use reqwest::Response;
+use std::time::Duration;
+
+use super::cache::CacheOptions;
+use super::PagerDutyError;
+
+const BASE_DELAY_MS: u64 = 500;
+const MAX_DELAY_MS: u64 = 30_000;
+/// PagerDuty's rate limits reset every minute, so a longer wait is a bad header.
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+/// Controls how hard the client will try before giving up on PagerDuty.
+#[derive(Debug, Clone)]
+pub struct RequestOptions {
+    /// Total number of times a single request is attempted, including the first.
+    pub max_attempts: u32,
+    /// Requests in flight at once, shared across every resource being fetched.
+    pub max_concurrent_requests: usize,
+    pub cache: CacheOptions,
+}
+
+impl std::default::Default for RequestOptions {
+    fn default() -> Self {
+        RequestOptions {
+            max_attempts: 5,
+            max_concurrent_requests: 4,
+            cache: CacheOptions::default(),
+        }
+    }
+}
+
+/// Whether a request that failed with `error` is worth sending again. Only a rate limit is
+/// sure to mean PagerDuty didn't act on it, so that's all a non-`idempotent` request is
+/// retried for, as a server error or timeout could still have created something.
+pub(crate) fn is_retryable(error: &PagerDutyError, idempotent: bool) -> bool {
+    match error {
+        PagerDutyError::RateLimited { .. } => true,
+        PagerDutyError::HttpStatus { status, .. } => idempotent && *status >= 500,
+        PagerDutyError::Network(_) => idempotent,
+        _ => false,
+    }
+}
+
+/// How long to wait before making attempt number `attempt + 1`.
+///
+/// When PagerDuty tells us how long to wait that is used, up to a minute, otherwise the
+/// delay doubles every attempt (capped at 30 seconds) with up to 100% jitter added.
+pub(crate) fn backoff_delay(attempt: u32, error: &PagerDutyError) -> Duration {
+    if let PagerDutyError::RateLimited {
+        retry_after: Some(seconds),
+    } = error
+    {
+        return Duration::from_secs((*seconds).min(MAX_RETRY_AFTER_SECS));
+    }
+
+    let exponent = attempt.saturating_sub(1).min(16);
+    let delay = BASE_DELAY_MS
+        .saturating_mul(1 << exponent)
+        .min(MAX_DELAY_MS);
+    let jitter = rand::thread_rng().gen_range(0, delay + 1);
+
+    Duration::from_millis((delay + jitter).min(MAX_DELAY_MS))
+}
+
+/// PagerDuty sends `Retry-After` on some 429s and `ratelimit-reset` on others, both in seconds.
+pub(crate) fn retry_after_seconds(resp: &Response) -> Option<u64> {
+    ["Retry-After", "ratelimit-reset"].iter().find_map(|name| {
+        resp.headers()
+            .get(*name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn retry_after_is_honored() {
+        let error = PagerDutyError::RateLimited {
+            retry_after: Some(7),
+        };
+        assert_eq!(Duration::from_secs(7), backoff_delay(1, &error));
+        assert_eq!(Duration::from_secs(7), backoff_delay(4, &error));
+    }
+
+    #[test]
+    fn retry_after_is_capped() {
+        let error = PagerDutyError::RateLimited {
+            retry_after: Some(86_400),
+        };
+        assert_eq!(
+            Duration::from_secs(MAX_RETRY_AFTER_SECS),
+            backoff_delay(1, &error)
+        );
+    }
+
+    #[test]
+    fn delay_grows_and_is_capped() {
+        let error = PagerDutyError::RateLimited { retry_after: None };
+
+        let first = backoff_delay(1, &error);
+        assert!(first >= Duration::from_millis(500));
+        assert!(first <= Duration::from_millis(1000));
+
+        let third = backoff_delay(3, &error);
+        assert!(third >= Duration::from_millis(2000));
+        assert!(third <= Duration::from_millis(4000));
+
+        assert!(backoff_delay(100, &error) <= Duration::from_millis(MAX_DELAY_MS));
+    }
+
+    #[test]
+    fn only_transient_errors_are_retried() {
+        assert!(is_retryable(
+            &PagerDutyError::from_status(429, "", None),
+            true
+        ));
+        assert!(is_retryable(
+            &PagerDutyError::from_status(503, "", None),
+            true
+        ));
+        assert!(!is_retryable(
+            &PagerDutyError::from_status(401, "", None),
+            true
+        ));
+        assert!(!is_retryable(
+            &PagerDutyError::from_status(404, "", None),
+            true
+        ));
+    }
+
+    #[test]
+    fn non_idempotent_requests_are_only_retried_when_rate_limited() {
+        assert!(is_retryable(
+            &PagerDutyError::from_status(429, "", None),
+            false
+        ));
+        assert!(!is_retryable(
+            &PagerDutyError::from_status(503, "", None),
+            false
+        ));
+    }
+}
@@ -0,0 +1,1199 @@
+mod common;
+
+use common::{
+    config_path, run_cli, run_with_config, run_without_token, MockPagerDuty, ACCOUNT_TOKEN,
+    READ_ONLY_TOKEN, REVOKED_TOKEN,
+};
+
+#[test]
+fn who_is_oncall_reads_every_page() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&["who-is-oncall", "--format", "csv"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(
+        "Escalation Policy ID,Escalation Policy,depth,name,email,shift start,shift end,schedule",
+        lines[0]
+    );
+    // The only oncall user present in the users fixture is on the 2nd page of users
+    assert!(stdout.contains("Escalation Policy adipisci itaque velit"));
+    assert!(stdout.contains(
+        "alexandra.vandervort@example.com,2015-11-09T00:00:00-05:00,2015-11-09T20:00:00-05:00,Payments Primary"
+    ));
+}
+
+#[test]
+fn export_contains_policies_from_every_page() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&["export"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let export: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+
+    let policies = export["escalation_policies"].as_object().unwrap();
+    assert_eq!(25, policies.len());
+    assert_eq!("P9OFD2O", policies["Default"]);
+}
+
+#[test]
+fn export_filters_policies_by_name() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&[
+        "export",
+        "--format",
+        "json",
+        "--include",
+        "^Default$",
+        "--include",
+        "adipisci itaque velit$",
+    ]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let export: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+
+    let ids: Vec<&str> = export
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|policy| policy["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(vec!["P9OFD2O", "P7DBLPX"], ids);
+    assert_eq!(
+        "user_reference",
+        export[0]["rules"][0]["targets"][0]["type"]
+    );
+}
+
+#[test]
+fn export_filter_flag_is_an_alias_for_include() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&["export", "--filter", "^Default$"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let export: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+
+    let policies = export["escalation_policies"].as_object().unwrap();
+    assert_eq!(1, policies.len());
+    assert_eq!("P9OFD2O", policies["Default"]);
+}
+
+#[test]
+fn export_terraform_emits_resources_and_import_blocks() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&["export", "--format", "terraform"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("resource \"pagerduty_escalation_policy\" \"default\" {"));
+    assert!(stdout.contains("  to = pagerduty_escalation_policy.default\n  id = \"P9OFD2O\"\n"));
+    assert!(stdout.contains("pagerduty_team.payments.id"));
+    assert!(stdout.contains("resource \"pagerduty_service\""));
+}
+
+#[test]
+fn unknown_endpoint_exits_with_http_status_code() {
+    let server = MockPagerDuty::start();
+    let output = run_cli(&format!("{}/missing", server.url()), &["who-is-oncall"]);
+
+    assert_eq!(Some(5), output.status.code());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn rate_limits_are_retried() {
+    let server = MockPagerDuty::start_rate_limited(3);
+    let output = server.run(&["export"]);
+
+    assert!(output.status.success());
+    // The run worked, so nothing it hit on the way is an error.
+    assert!(!String::from_utf8(output.stderr).unwrap().contains("ERROR"));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("P9OFD2O"));
+}
+
+#[test]
+fn rate_limit_exits_when_attempts_are_exhausted() {
+    let server = MockPagerDuty::start_rate_limited(100);
+    let output = server.run(&["--max-attempts", "2", "export"]);
+
+    assert_eq!(Some(4), output.status.code());
+}
+
+#[test]
+fn incidents_can_be_filtered_by_service() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&[
+        "incidents",
+        "--status",
+        "triggered",
+        "--service",
+        "eaque",
+        "--format",
+        "csv",
+    ]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().filter(|l| !l.is_empty()).collect();
+
+    assert_eq!(2, lines.len());
+    assert!(lines[1].starts_with("PZ7Y2KB,1236,"));
+    assert!(lines[1].contains("\"John Doe, Ola Schoen\""));
+}
+
+#[test]
+fn incident_ack_reports_each_incident() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&[
+        "incident",
+        "ack",
+        "--from",
+        "oncall@example.com",
+        "PQ1GN4X",
+        "PMISSING",
+    ]);
+
+    assert_eq!(Some(5), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("PQ1GN4X - ok"));
+    assert!(stdout.contains("PMISSING - failed"));
+    assert!(stdout.contains("1 succeeded, 1 failed"));
+
+    let writes = server.writes();
+    assert_eq!(2, writes.len());
+    assert!(writes.iter().all(|w| w.method == "PUT"));
+    assert!(writes
+        .iter()
+        .all(|w| w.from.as_deref() == Some("oncall@example.com")));
+    assert_eq!("acknowledged", writes[0].body["incident"]["status"]);
+}
+
+#[test]
+fn incident_snooze_uses_filters() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&[
+        "incident",
+        "snooze",
+        "--from",
+        "oncall@example.com",
+        "--for",
+        "2h",
+        "--service",
+        "eaque",
+    ]);
+
+    assert!(output.status.success());
+
+    let writes = server.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("POST", writes[0].method);
+    assert_eq!("/incidents/PZ7Y2KB/snooze", writes[0].path);
+    assert_eq!(7200, writes[0].body["duration"]);
+}
+
+#[test]
+fn incident_merge_skips_the_target() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&[
+        "incident",
+        "merge",
+        "--from",
+        "oncall@example.com",
+        "--into",
+        "PZ7Y2KB",
+        "PZ7Y2KB",
+        "PQ1GN4X",
+    ]);
+
+    assert!(output.status.success());
+
+    let writes = server.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("/incidents/PZ7Y2KB/merge", writes[0].path);
+    assert_eq!("PQ1GN4X", writes[0].body["source_incidents"][0]["id"]);
+}
+
+#[test]
+fn schedules_render_layers_and_final_schedule() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&[
+        "schedules",
+        "--include",
+        "Payments",
+        "--since",
+        "2015-11-09T00:00:00-05:00",
+        "--until",
+        "2015-11-10T00:00:00-05:00",
+    ]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("Schedule - Payments Primary (America/New_York)"));
+    assert!(stdout.contains("Layer 1 - rotates every 1d - Earline Greenholt, Kristina Gerlach"));
+    assert!(stdout.contains("Final Schedule"));
+    assert!(!stdout.contains("Checkout Secondary"));
+}
+
+#[test]
+fn who_is_oncall_at_a_time_lists_shifts() {
+    let server = MockPagerDuty::start();
+    let output = server.run(&[
+        "who-is-oncall",
+        "--at",
+        "2015-11-09T12:00:00-05:00",
+        "--include",
+        "adipisci itaque",
+    ]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("Level 1"));
+    assert!(stdout.contains(
+        "2015-11-09T00:00:00-05:00 to 2015-11-09T20:00:00-05:00 - Alexandra Vandervort (alexandra.vandervort@example.com) via Payments Primary"
+    ));
+}
+
+#[test]
+fn oncall_for_me_lists_policies_and_levels() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["oncall", "--user", "me", "--format", "csv"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = stdout
+        .lines()
+        .skip(1)
+        .filter(|line| !line.is_empty())
+        .collect();
+    assert!(!rows.is_empty());
+    assert!(rows.iter().all(|row| row.contains("Alexandra Vandervort")));
+    assert!(rows
+        .iter()
+        .any(|row| row.contains("Escalation Policy adipisci itaque velit,2,")));
+}
+
+#[test]
+fn oncall_for_unknown_user_exits_with_not_found() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["oncall", "--user", "nobody@example.com"]);
+    assert_eq!(output.status.code(), Some(8));
+}
+
+#[test]
+fn override_create_posts_to_the_named_schedule() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&[
+        "override",
+        "create",
+        "--schedule",
+        "payments primary",
+        "--user",
+        "me",
+        "--start",
+        "2015-11-09T12:00:00Z",
+        "--end",
+        "2015-11-09T18:00:00Z",
+    ]);
+    assert!(output.status.success());
+
+    let writes = mock.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("POST", writes[0].method);
+    assert_eq!("/schedules/PI7DH85/overrides", writes[0].path);
+    assert_eq!("P31O4ZX", writes[0].body["override"]["user"]["id"]);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("PNEW001"));
+}
+
+#[test]
+fn override_dry_run_previews_without_writing() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&[
+        "override",
+        "create",
+        "--schedule",
+        "PI7DH85",
+        "--user",
+        "me",
+        "--start",
+        "2015-11-09T17:00:00Z",
+        "--end",
+        "2015-11-09T23:00:00Z",
+        "--dry-run",
+    ]);
+    assert!(output.status.success());
+    assert!(mock.writes().is_empty());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Final Schedule (preview)"));
+    assert!(stdout
+        .contains("2015-11-09T17:00:00+00:00 to 2015-11-09T23:00:00+00:00 - Alexandra Vandervort"));
+    assert!(stdout
+        .contains("2015-11-09T00:00:00-05:00 to 2015-11-09T17:00:00+00:00 - Earline Greenholt"));
+}
+
+#[test]
+fn override_list_and_delete() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&[
+        "override",
+        "list",
+        "--schedule",
+        "Payments Primary",
+        "--format",
+        "csv",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("PI7DH85,Payments Primary,PQ47DCP,"));
+    assert!(stdout.contains("PT9ZNWK"));
+
+    let output = mock.run(&[
+        "override",
+        "delete",
+        "--schedule",
+        "Payments Primary",
+        "PQ47DCP",
+    ]);
+    assert!(output.status.success());
+
+    let writes = mock.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("DELETE", writes[0].method);
+    assert_eq!("/schedules/PI7DH85/overrides/PQ47DCP", writes[0].path);
+}
+
+#[test]
+fn override_refuses_schedule_names_shared_by_more_than_one() {
+    let mock = MockPagerDuty::start();
+
+    let args = [
+        "override",
+        "delete",
+        "--schedule",
+        "Checkout Secondary",
+        "PQ47DCP",
+    ];
+    let output = mock.run(&args);
+    assert_eq!(Some(9), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("PQ8ZC2N"));
+    assert!(stderr.contains("PD4PL1C"));
+    assert!(mock.writes().is_empty());
+
+    // An ID is never ambiguous.
+    let output = mock.run(&["override", "delete", "--schedule", "PD4PL1C", "PQ47DCP"]);
+    assert!(output.status.success());
+    assert_eq!(
+        "/schedules/PD4PL1C/overrides/PQ47DCP",
+        mock.writes()[0].path
+    );
+}
+
+#[test]
+fn override_for_unknown_schedule_exits_with_not_found() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["override", "list", "--schedule", "Nope"]);
+    assert_eq!(Some(8), output.status.code());
+}
+
+#[test]
+fn maintenance_start_selects_services_by_regex() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&[
+        "maintenance",
+        "start",
+        "--from",
+        "me@example.com",
+        "--service",
+        "^Service a ",
+        "--service",
+        "nihil$",
+        "--duration",
+        "30m",
+        "--description",
+        "Deploying payments",
+    ]);
+    assert!(output.status.success());
+
+    let writes = mock.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("POST", writes[0].method);
+    assert_eq!("/maintenance_windows", writes[0].path);
+    assert_eq!(Some("me@example.com".to_string()), writes[0].from);
+
+    let window = &writes[0].body["maintenance_window"];
+    assert_eq!("Deploying payments", window["description"]);
+    let services: Vec<&str> = window["services"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|service| service["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(vec!["PC8O0L3", "PYFZWI0"], services);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Maintenance Window - Deploying payments (PNEW001)"));
+
+    for duration in &["0m", "-30", "100000000w"] {
+        let output = mock.run(&[
+            "maintenance",
+            "start",
+            "--from",
+            "me@example.com",
+            "--service",
+            "nihil$",
+            "--duration",
+            duration,
+        ]);
+        assert!(!output.status.success());
+    }
+    assert_eq!(1, mock.writes().len());
+}
+
+#[test]
+fn maintenance_list_and_end() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["maintenance", "list", "--ongoing", "--format", "csv"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("PW98YIO,1,"));
+    assert!(mock
+        .requests()
+        .iter()
+        .any(|request| request.path == "/maintenance_windows"
+            && request.query.contains("filter=ongoing")));
+
+    let output = mock.run(&["maintenance", "end", "PW98YIO"]);
+    assert!(output.status.success());
+
+    let writes = mock.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("DELETE", writes[0].method);
+    assert_eq!("/maintenance_windows/PW98YIO", writes[0].path);
+}
+
+#[test]
+fn event_trigger_sends_payload_and_details() {
+    let mock = MockPagerDuty::start();
+
+    let details = std::env::temp_dir().join(format!("pd-details-{}.json", std::process::id()));
+    std::fs::write(&details, r#"{"disk": "/var", "free": "10%"}"#).unwrap();
+
+    let output = run_cli(
+        "http://127.0.0.1:1",
+        &[
+            "event",
+            "trigger",
+            "--events-url",
+            &mock.url(),
+            "--routing-key",
+            "R0UT1NGK3Y",
+            "--summary",
+            "Disk is almost full",
+            "--source",
+            "db-1",
+            "--severity",
+            "warning",
+            "--class",
+            "disk",
+            "--details-file",
+            details.to_str().unwrap(),
+            "--detail",
+            "free=5%",
+            "--link",
+            "https://example.com/dash|Dashboard",
+            "--image",
+            "https://example.com/graph.png",
+        ],
+    );
+    std::fs::remove_file(&details).ok();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("generated-dedup-key"));
+
+    let writes = mock.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("/v2/enqueue", writes[0].path);
+
+    let body = &writes[0].body;
+    assert_eq!("R0UT1NGK3Y", body["routing_key"]);
+    assert_eq!("trigger", body["event_action"]);
+    assert_eq!("warning", body["payload"]["severity"]);
+    assert_eq!("disk", body["payload"]["class"]);
+    assert_eq!("/var", body["payload"]["custom_details"]["disk"]);
+    assert_eq!("5%", body["payload"]["custom_details"]["free"]);
+    assert_eq!("Dashboard", body["links"][0]["text"]);
+    assert_eq!("https://example.com/graph.png", body["images"][0]["src"]);
+}
+
+#[test]
+fn event_resolve_requires_dedup_key() {
+    let mock = MockPagerDuty::start();
+    let events_url = mock.url();
+    let args = [
+        "event",
+        "resolve",
+        "--events-url",
+        &events_url,
+        "--routing-key",
+        "R0UT1NGK3Y",
+    ];
+
+    let output = mock.run(&args);
+    assert!(!output.status.success());
+    assert!(mock.writes().is_empty());
+
+    let output = mock.run(&[&args[..], &["--dedup-key", "disk-full"]].concat());
+    assert!(output.status.success());
+    assert_eq!("disk-full", mock.writes()[0].body["dedup_key"]);
+    assert!(mock.writes()[0].body.get("payload").is_none());
+}
+
+#[test]
+fn triggers_are_only_sent_again_when_they_have_a_dedup_key() {
+    let mock = MockPagerDuty::start_failing("500 Internal Server Error", 100);
+    let events_url = mock.url();
+    let args = [
+        "--max-attempts",
+        "2",
+        "event",
+        "trigger",
+        "--events-url",
+        &events_url,
+        "--routing-key",
+        "R0UT1NGK3Y",
+        "--summary",
+        "Disk is almost full",
+        "--source",
+        "db-1",
+        "--severity",
+        "error",
+    ];
+
+    // The first attempt may have opened an incident, sending it again could open another.
+    let output = mock.run(&args);
+    assert_eq!(Some(5), output.status.code());
+    assert_eq!(1, mock.writes().len());
+
+    let output = mock.run(&[&args[..], &["--dedup-key", "disk-full"]].concat());
+    assert_eq!(Some(5), output.status.code());
+    assert_eq!(3, mock.writes().len());
+}
+
+#[test]
+fn change_event_is_sent_to_the_change_endpoint() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&[
+        "change-event",
+        "--events-url",
+        &mock.url(),
+        "--routing-key",
+        "R0UT1NGK3Y",
+        "--summary",
+        "Deployed payments v1.2.3",
+        "--detail",
+        "version=1.2.3",
+    ]);
+    assert!(output.status.success());
+
+    let writes = mock.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("/v2/change/enqueue", writes[0].path);
+    assert_eq!(
+        "Deployed payments v1.2.3",
+        writes[0].body["payload"]["summary"]
+    );
+    assert_eq!(
+        "1.2.3",
+        writes[0].body["payload"]["custom_details"]["version"]
+    );
+}
+
+#[test]
+fn responses_are_cached_between_runs() {
+    let mock = MockPagerDuty::start();
+
+    let first = mock.run(&["who-is-oncall", "--format", "csv"]);
+    assert!(first.status.success());
+    let fetched = mock.requests().len();
+    assert!(fetched > 0);
+
+    let second = mock.run(&["who-is-oncall", "--format", "csv"]);
+    assert!(second.status.success());
+    assert_eq!(first.stdout, second.stdout);
+    assert_eq!(fetched, mock.requests().len());
+
+    let refreshed = mock.run(&["who-is-oncall", "--format", "csv", "--refresh"]);
+    assert!(refreshed.status.success());
+    assert_eq!(fetched * 2, mock.requests().len());
+
+    let uncached = mock.run(&["who-is-oncall", "--format", "csv", "--no-cache"]);
+    assert!(uncached.status.success());
+    assert_eq!(fetched * 3, mock.requests().len());
+}
+
+#[test]
+fn offline_only_reads_the_cache() {
+    let mock = MockPagerDuty::start();
+
+    let missing = mock.run(&["who-is-oncall", "--offline"]);
+    assert_eq!(Some(10), missing.status.code());
+    assert!(mock.requests().is_empty());
+
+    let online = mock.run(&["who-is-oncall", "--format", "csv"]);
+    let fetched = mock.requests().len();
+
+    let offline = mock.run(&["who-is-oncall", "--format", "csv", "--offline"]);
+    assert!(offline.status.success());
+    assert_eq!(online.stdout, offline.stdout);
+    assert_eq!(fetched, mock.requests().len());
+}
+
+#[test]
+fn profiles_supply_the_token_url_and_defaults() {
+    let mock = MockPagerDuty::start();
+    let config = format!(
+        "default_profile = \"mock\"\n\n[profiles.mock]\ntoken = \"profile-token\"\napi_url = \"{}\"\nformat = \"csv\"\ninclude = [\"adipisci itaque velit$\"]\n",
+        mock.url()
+    );
+
+    let output = run_with_config(&mock.url(), &config, &["who-is-oncall"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = stdout
+        .lines()
+        .skip(1)
+        .filter(|line| !line.is_empty())
+        .collect();
+    assert!(!rows.is_empty());
+    assert!(rows.iter().all(|row| row.starts_with("P7DBLPX,")));
+
+    let output = run_with_config(&mock.url(), &config, &["who-is-oncall", "--format", "json"]);
+    assert!(output.status.success());
+    let oncalls: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(oncalls
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|oncall| oncall["id"] == "P7DBLPX"));
+
+    let incidents = run_with_config(&mock.url(), &config, &["incidents"]);
+    assert!(incidents.status.success());
+    assert!(String::from_utf8(incidents.stdout)
+        .unwrap()
+        .starts_with("Incident ID,"));
+    let teams = run_with_config(&mock.url(), &config, &["teams"]);
+    assert!(teams.status.success());
+    assert!(String::from_utf8(teams.stdout)
+        .unwrap()
+        .starts_with("Team ID,Team,"));
+    // `export` feeds Terraform, so it ignores the profile's format.
+    let export = run_with_config(&mock.url(), &config, &["export"]);
+    assert!(export.status.success());
+    let tfstate: serde_json::Value = serde_json::from_slice(&export.stdout).unwrap();
+    assert!(tfstate["escalation_policies"].is_object());
+    // `whoami` can't write csv, so it keeps its own default.
+    let whoami = run_with_config(&mock.url(), &config, &["whoami"]);
+    assert!(whoami.status.success());
+    assert!(!String::from_utf8(whoami.stdout).unwrap().contains(','));
+
+    let output = run_with_config(
+        &mock.url(),
+        &config,
+        &["who-is-oncall", "--profile", "staging"],
+    );
+    assert_eq!(Some(8), output.status.code());
+
+    let broken = config.replace("adipisci itaque velit$", "(");
+    let output = run_with_config(&mock.url(), &broken, &["who-is-oncall"]);
+    assert_eq!(Some(11), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("`(` isn't a regex"));
+}
+
+#[test]
+fn config_subcommand_manages_profiles() {
+    let mock = MockPagerDuty::start();
+    let url = mock.url();
+
+    let set = run_with_config(
+        &url,
+        "",
+        &["config", "set", "sandbox", "token", "u+abcdefghijklmnop"],
+    );
+    assert!(set.status.success());
+    let bad = mock.run(&["config", "set", "sandbox", "format", "yaml"]);
+    assert_eq!(Some(11), bad.status.code());
+    assert!(mock.run(&["config", "use", "sandbox"]).status.success());
+
+    let list = String::from_utf8(mock.run(&["config", "list"]).stdout).unwrap();
+    assert!(list.contains("Profile - sandbox (default)"));
+    assert!(list.contains("token - ****mnop"));
+    assert!(!list.contains("yaml"));
+
+    assert!(mock.run(&["config", "validate"]).status.success());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(config_path(&url))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(0o600, mode & 0o777);
+    }
+
+    std::fs::write(config_path(&url), "[profiles.sandbox]\ntokn = \"abc\"\n").unwrap();
+    let invalid = mock.run(&["config", "validate"]);
+    assert_eq!(Some(11), invalid.status.code());
+    let stderr = String::from_utf8(invalid.stderr).unwrap();
+    assert!(stderr.contains("unknown field `tokn`"));
+}
+
+#[test]
+fn login_saves_the_token_for_later_commands() {
+    let mock = MockPagerDuty::start();
+    let url = mock.url();
+    let credentials = config_path(&url).with_file_name("credentials.toml");
+
+    let login = run_without_token(&url, &["login"], "typed-token\n");
+    assert!(login.status.success());
+    let stdout = String::from_utf8(login.stdout).unwrap();
+    assert!(stdout.starts_with("Logged in to profile default as "));
+    assert!(std::fs::read_to_string(&credentials)
+        .unwrap()
+        .contains("token = \"typed-token\""));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&credentials)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(0o600, mode & 0o777);
+    }
+
+    let oncall = run_without_token(&url, &["who-is-oncall"], "");
+    assert!(oncall.status.success());
+    assert_eq!(
+        Some("Token token=typed-token".to_string()),
+        mock.requests().last().unwrap().authorization
+    );
+
+    let logout = run_without_token(&url, &["logout"], "");
+    assert_eq!(
+        "Logged out of profile default\n",
+        String::from_utf8(logout.stdout).unwrap()
+    );
+    let again = run_without_token(&url, &["logout"], "");
+    assert_eq!(
+        "Not logged in to profile default\n",
+        String::from_utf8(again.stdout).unwrap()
+    );
+}
+
+#[test]
+fn login_can_save_a_token_command_instead() {
+    let mock = MockPagerDuty::start();
+    let url = mock.url();
+
+    let login = run_without_token(
+        &url,
+        &[
+            "login",
+            "--profile",
+            "work",
+            "--token-command",
+            "echo command-token",
+        ],
+        "",
+    );
+    assert!(login.status.success());
+    let saved =
+        std::fs::read_to_string(config_path(&url).with_file_name("credentials.toml")).unwrap();
+    assert!(saved.contains("token_command = \"echo command-token\""));
+    assert!(!saved.contains("token = "));
+
+    let oncall = run_without_token(&url, &["who-is-oncall", "--profile", "work"], "");
+    assert!(oncall.status.success());
+    assert_eq!(
+        Some("Token token=command-token".to_string()),
+        mock.requests().last().unwrap().authorization
+    );
+
+    let failing = run_without_token(&url, &["login", "--token-command", "exit 1"], "");
+    assert_eq!(Some(12), failing.status.code());
+}
+
+#[test]
+fn login_checks_the_token_before_saving_it() {
+    let mock = MockPagerDuty::start();
+    let url = mock.url();
+
+    let revoked = run_without_token(&url, &["login", "--api-token", REVOKED_TOKEN], "");
+    assert_eq!(Some(3), revoked.status.code());
+    assert!(!config_path(&url)
+        .with_file_name("credentials.toml")
+        .exists());
+
+    let account = run_without_token(&url, &["login", "--api-token", ACCOUNT_TOKEN], "");
+    assert!(account.status.success());
+    assert_eq!(
+        "Logged in to profile default with an account token\n",
+        String::from_utf8(account.stdout).unwrap()
+    );
+    assert!(mock
+        .requests()
+        .iter()
+        .any(|request| request.path == "/abilities"));
+}
+
+#[test]
+fn whoami_reports_the_user_account_and_abilities() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["whoami"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Token - user token"));
+    assert!(stdout.contains("Role - limited_user"));
+    assert!(stdout.contains("Account - apidocs"));
+    assert!(stdout.contains("manage_schedules"));
+
+    let output = run_without_token(
+        &mock.url(),
+        &["--api-token", ACCOUNT_TOKEN, "whoami", "--format", "json"],
+        "",
+    );
+    assert!(output.status.success());
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("account", info["tokenType"]);
+    assert!(info["user"].is_null());
+    assert_eq!("apidocs", info["subdomain"]);
+}
+
+#[test]
+fn abilities_checks_the_ones_asked_for() {
+    let mock = MockPagerDuty::start();
+
+    let all = String::from_utf8(mock.run(&["abilities"]).stdout).unwrap();
+    assert!(all.lines().any(|line| line == "teams"));
+
+    let output = mock.run(&["abilities", "teams", "advanced_reports"]);
+    assert_eq!(Some(13), output.status.code());
+    assert_eq!(
+        "teams - yes\nadvanced_reports - no\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn read_only_users_are_stopped_before_writing() {
+    let mock = MockPagerDuty::start();
+
+    let output = run_without_token(
+        &mock.url(),
+        &[
+            "--api-token",
+            READ_ONLY_TOKEN,
+            "incident",
+            "ack",
+            "PT4KHLK",
+            "--from",
+            "earline@example.com",
+        ],
+        "",
+    );
+    assert_eq!(Some(13), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("read_only_user"));
+    assert!(mock.writes().is_empty());
+}
+
+#[test]
+fn teams_lists_members_and_what_they_own() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["teams", "--include", "^Pay"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Team - Payments (PQ9K7I8)"));
+    assert!(stdout.contains("Abagail Hickle - manager"));
+    assert!(stdout.contains("Adalberto Ryan - responder"));
+    assert!(stdout.contains("Default"));
+    assert!(stdout.contains("Service a eaque non"));
+    assert!(!stdout.contains("Checkout"));
+
+    let output = mock.run(&["teams", "--team", "checkout", "--format", "json"]);
+    assert!(output.status.success());
+    let teams: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(1, teams.as_array().unwrap().len());
+    assert_eq!("PGVEH2X", teams[0]["id"]);
+    assert!(teams[0]["members"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn team_filter_applies_to_listings() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["--team", "Payments", "who-is-oncall"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Escilation Policy - Default"));
+    assert!(!stdout.contains("adipisci itaque velit"));
+
+    let output = mock.run(&["export", "--team", "PQ9K7I8", "--format", "json"]);
+    assert!(output.status.success());
+    let policies: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(1, policies.as_array().unwrap().len());
+    assert_eq!("P9OFD2O", policies[0]["id"]);
+
+    let output = mock.run(&["incidents", "--team", "Checkout"]);
+    assert!(output.status.success());
+    assert!(mock
+        .requests()
+        .iter()
+        .any(|request| request.path == "/incidents" && request.query.contains("PGVEH2X")));
+
+    let output = mock.run(&["schedules", "--team", "Sales"]);
+    assert_eq!(Some(8), output.status.code());
+}
+
+#[test]
+fn team_filter_applies_to_maintenance_and_overrides() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&[
+        "--team",
+        "Payments",
+        "maintenance",
+        "list",
+        "--format",
+        "csv",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("PW98YIO"));
+    assert!(!stdout.contains("PQ2LM5A"));
+
+    let output = mock.run(&[
+        "--team",
+        "Checkout",
+        "override",
+        "list",
+        "--schedule",
+        "PI7DH85",
+        "--format",
+        "json",
+    ]);
+    assert!(output.status.success());
+    let overrides: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!overrides.as_array().unwrap().is_empty());
+
+    let output = mock.run(&[
+        "--team",
+        "Payments",
+        "override",
+        "list",
+        "--schedule",
+        "PI7DH85",
+        "--format",
+        "json",
+    ]);
+    assert!(output.status.success());
+    let overrides: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(overrides.as_array().unwrap().is_empty());
+}
+
+#[test]
+fn services_lists_settings_and_integrations() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["services", "--include", "accusantium"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Service - Service accusantium nisi voluptatem (PX01HJD)"));
+    assert!(stdout.contains("Status - warning"));
+    assert!(stdout.contains("Escalation Policy - Default (P9OFD2O)"));
+    assert!(stdout.contains("Urgency - high during support hours, low outside"));
+    assert!(stdout.contains("Acknowledgement Timeout - 30m"));
+    assert!(stdout.contains("Auto Resolve Timeout - 4h"));
+    assert!(stdout.contains("Alert Grouping - time, 5m"));
+    assert!(stdout.contains("Datadog (Datadog) - 8d6a0c1b2e3f4a5b6c7d8e9f0a1b2c3d"));
+    assert!(!stdout.contains("Service a eaque non"));
+    assert!(mock
+        .requests()
+        .iter()
+        .any(|request| request.path == "/services" && request.query.contains("integrations")));
+
+    let output = mock.run(&["--team", "Payments", "services", "--format", "json"]);
+    assert!(output.status.success());
+    let services: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(1, services.as_array().unwrap().len());
+    assert_eq!("PC8O0L3", services[0]["id"]);
+    assert_eq!("constant", services[0]["urgency"]["type"]);
+    assert!(services[0]["alertGrouping"].is_null());
+    assert_eq!("Events API v1", services[0]["integrations"][0]["name"]);
+}
+
+#[test]
+fn who_is_oncall_filters_and_shows_policies_by_service() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&[
+        "who-is-oncall",
+        "--service",
+        "accusantium",
+        "--show-services",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Escilation Policy - Default"));
+    assert!(stdout.contains("Services"));
+    assert!(stdout.contains("Service accusantium nisi voluptatem"));
+    assert!(!stdout.contains("adipisci itaque velit"));
+
+    let output = mock.run(&["who-is-oncall", "--service", "^nothing$"]);
+    assert!(output.status.success());
+    assert!(!String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Escilation Policy"));
+
+    let output = mock.run(&["who-is-oncall", "--show-services", "--format", "json"]);
+    assert!(output.status.success());
+    let rows: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(rows
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|row| row["services"].is_array()));
+
+    let output = mock.run(&["who-is-oncall", "--show-services", "--format", "csv"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .next()
+        .unwrap()
+        .ends_with(",schedule,services"));
+}
+
+#[test]
+fn page_who_shows_the_chain_for_a_service() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["page-who", "service AD IN corrupti", "--no-cache"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Service - Service ad in corrupti (P5FK83M) - active"));
+    assert!(
+        stdout.contains("Escalation Policy - Escalation Policy adipisci itaque velit (P7DBLPX)")
+    );
+    assert!(stdout.contains("Level 1, escalates after 17m"));
+    assert!(stdout.contains("Nobody on call"));
+    assert!(stdout.contains("Level 2, escalates after 9m"));
+    assert!(stdout.contains("Alexandra Vandervort (alexandra.vandervort@example.com)"));
+    assert!(stdout.contains("phone - work - +1 5555550123"));
+    assert!(stdout.contains("push - Pixel 7"));
+    assert!(mock.writes().is_empty());
+    let service_pages = mock
+        .requests()
+        .iter()
+        .filter(|request| request.path == "/services" && request.query.contains("offset=0"))
+        .count();
+    assert_eq!(1, service_pages);
+
+    let output = mock.run(&["page-who", "^Service adipisci v", "--format", "json"]);
+    assert!(output.status.success());
+    let chains: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(3, chains.as_array().unwrap().len());
+
+    let output = mock.run(&[
+        "page-who",
+        "^Service ad",
+        "--open-incident",
+        "Checkout is down",
+    ]);
+    assert_eq!(Some(9), output.status.code());
+
+    let output = mock.run(&["page-who", "nothing like this"]);
+    assert_eq!(Some(8), output.status.code());
+}
+
+#[test]
+fn page_who_opens_an_incident_on_the_service() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&[
+        "page-who",
+        "P5FK83M",
+        "--open-incident",
+        "Checkout is down",
+        "--urgency",
+        "low",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Opened incident #1234 on Service ad in corrupti"));
+
+    let writes = mock.writes();
+    assert_eq!(1, writes.len());
+    assert_eq!("/incidents", writes[0].path);
+    assert_eq!(
+        Some("alexandra.vandervort@example.com"),
+        writes[0].from.as_deref()
+    );
+    assert_eq!("P5FK83M", writes[0].body["incident"]["service"]["id"]);
+    assert_eq!("low", writes[0].body["incident"]["urgency"]);
+}
+
+#[test]
+fn users_lists_contact_methods_and_flags_who_is_not_ready() {
+    let mock = MockPagerDuty::start();
+
+    let output = mock.run(&["users", "--include", "^Al(exandra|f)"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("User - Alexandra Vandervort (alexandra.vandervort@example.com) (P31O4ZX)")
+    );
+    assert!(stdout.contains("Job Title - Site Reliability Engineer"));
+    assert!(stdout.contains("phone - work - +1 5555550123"));
+    assert!(stdout.contains("after 0m - phone - work"));
+    assert!(stdout.contains("after 5m - sms - cell"));
+    assert!(stdout.contains("no high-urgency notification rule"));
+    assert!(mock
+        .requests()
+        .iter()
+        .any(|request| request.path == "/users" && request.query.contains("notification_rules")));
+
+    let output = mock.run(&["users", "--not-ready", "--format", "json"]);
+    assert!(output.status.success());
+    let users: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let users = users.as_array().unwrap();
+    assert!(users.iter().all(|user| user["id"] != "P31O4ZX"));
+    let alf = users.iter().find(|user| user["id"] == "P2DQNMZ").unwrap();
+    assert_eq!(2, alf["problems"].as_array().unwrap().len());
+}
@@ -0,0 +1,499 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{json, Value};
+
+/// The mock will never return more than this many records per page, regardless of
+/// the `limit` the client asked for, so that paging is always exercised.
+const MOCK_PAGE_LIMIT: usize = 10;
+
+/// Rejected with a 401, like a token that was deleted in PagerDuty.
+pub const REVOKED_TOKEN: &str = "revoked-token";
+/// An account level token, which has no user so `/users/me` is a 400.
+pub const ACCOUNT_TOKEN: &str = "account-token";
+/// Belongs to a user with the `read_only_user` role.
+pub const READ_ONLY_TOKEN: &str = "read-only-token";
+
+/// A stand-in for the PagerDuty REST API that replays the fixtures in
+/// `resources/test`, honoring `offset` and `limit` the same way PagerDuty does.
+///
+/// Writes are accepted for any record present in the fixtures, echoed back, and
+/// recorded so tests can assert on what was sent.
+pub struct MockPagerDuty {
+    port: u16,
+    state: Arc<MockState>,
+}
+
+struct MockState {
+    fixtures: BTreeMap<&'static str, Value>,
+    failures: AtomicUsize,
+    failure_status: &'static str,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub from: Option<String>,
+    pub authorization: Option<String>,
+    pub body: Value,
+}
+
+impl MockPagerDuty {
+    pub fn start() -> Self {
+        MockPagerDuty::start_rate_limited(0)
+    }
+
+    /// Answers the first `failures` requests with a 429 before serving fixtures.
+    pub fn start_rate_limited(failures: usize) -> Self {
+        MockPagerDuty::start_failing("429 Too Many Requests", failures)
+    }
+
+    /// Answers the first `failures` requests with `status` before serving fixtures.
+    pub fn start_failing(status: &'static str, failures: usize) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("to be able to bind a port");
+        let port = listener.local_addr().unwrap().port();
+
+        let mut fixtures = BTreeMap::new();
+        fixtures.insert(
+            "/escalation_policies",
+            load_fixture("escalation_policy.json"),
+        );
+        fixtures.insert("/oncalls", load_fixture("oncalls.json"));
+        fixtures.insert("/users", load_fixture("users.json"));
+        fixtures.insert("/services", load_fixture("services.json"));
+        fixtures.insert("/incidents", load_fixture("incidents.json"));
+        fixtures.insert("/schedules", load_fixture("schedules.json"));
+        fixtures.insert("/teams", load_fixture("teams.json"));
+        fixtures.insert("/abilities", load_fixture("abilities.json"));
+        fixtures.insert("/teams/PQ9K7I8/members", load_fixture("team_members.json"));
+        fixtures.insert(
+            "/users/P31O4ZX/contact_methods",
+            load_fixture("contact_methods.json"),
+        );
+        fixtures.insert(
+            "/teams/PGVEH2X/members",
+            json!({"members": [], "limit": 25, "offset": 0, "more": false}),
+        );
+        fixtures.insert(
+            "/maintenance_windows",
+            load_fixture("maintenance_windows.json"),
+        );
+        fixtures.insert("/schedules/PI7DH85", load_fixture("schedule.json"));
+        fixtures.insert(
+            "/schedules/PI7DH85/overrides",
+            load_fixture("overrides.json"),
+        );
+
+        // The owner of the mock token is the one user who is on call in the fixtures.
+        let me = fixtures["/users"]["users"][11].clone();
+        fixtures.insert("/users/me", json!({ "user": me }));
+
+        let state = Arc::new(MockState {
+            fixtures,
+            failures: AtomicUsize::new(failures),
+            failure_status: status,
+            requests: Mutex::new(Vec::new()),
+        });
+
+        let thread_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle_connection(stream, &thread_state);
+            }
+        });
+
+        MockPagerDuty { port, state }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        run_cli(&self.url(), args)
+    }
+
+    /// Every request, in the order they were received.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Every request other than a GET, in the order they were received.
+    pub fn writes(&self) -> Vec<RecordedRequest> {
+        self.state
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.method != "GET")
+            .cloned()
+            .collect()
+    }
+}
+
+pub fn run_cli(api_url: &str, args: &[&str]) -> Output {
+    command(api_url)
+        .args(["--api-token", "mock-token", "--api-url", api_url])
+        .args(args)
+        .output()
+        .expect("to be able to run pagerduty-cli")
+}
+
+/// Run with `config` as the config file, leaving the token and API URL to its profiles.
+pub fn run_with_config(api_url: &str, config: &str, args: &[&str]) -> Output {
+    std::fs::create_dir_all(scratch_dir(api_url)).unwrap();
+    std::fs::write(config_path(api_url), config).unwrap();
+
+    command(api_url)
+        .args(args)
+        .output()
+        .expect("to be able to run pagerduty-cli")
+}
+
+/// Run without a token, so it has to come from `login`, feeding `stdin` to the command.
+pub fn run_without_token(api_url: &str, args: &[&str], stdin: &str) -> Output {
+    let mut child = command(api_url)
+        .args(["--api-url", api_url])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("to be able to run pagerduty-cli");
+
+    // The command may exit without reading, which isn't a failure.
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).ok();
+    child
+        .wait_with_output()
+        .expect("to be able to run pagerduty-cli")
+}
+
+pub fn config_path(api_url: &str) -> PathBuf {
+    scratch_dir(api_url).join("config.toml")
+}
+
+/// Keeps the cache and config of the person running the tests out of the way.
+fn command(api_url: &str) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pagerduty-cli"));
+    command
+        .env("PAGERDUTY_CACHE_DIR", scratch_dir(api_url).join("cache"))
+        .env("PAGERDUTY_CONFIG", config_path(api_url))
+        .env_remove("PAGERDUTY_TOKEN")
+        .env_remove("PAGERDUTY_API_URL")
+        .env_remove("PAGERDUTY_PROFILE");
+    command
+}
+
+/// Each mock server listens on its own port, so gets its own cache and config.
+fn scratch_dir(api_url: &str) -> PathBuf {
+    let name: String = api_url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    std::env::temp_dir().join(format!("pd-cache-{}-{}", std::process::id(), name))
+}
+
+fn load_fixture(name: &str) -> Value {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push(format!("resources/test/{}", name));
+
+    let contents = std::fs::read_to_string(path).expect("fixture to exist");
+    serde_json::from_str(&contents).expect("fixture to be valid JSON")
+}
+
+fn handle_connection(mut stream: TcpStream, state: &MockState) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+
+    let mut content_length = 0;
+    let mut from = None;
+    let mut authorization = None;
+    loop {
+        let mut header = String::new();
+        match reader.read_line(&mut header) {
+            Ok(0) => break,
+            Ok(_) if header.trim().is_empty() => break,
+            Ok(_) => {
+                let mut parts = header.splitn(2, ':');
+                let name = parts.next().unwrap_or("").trim().to_lowercase();
+                let value = parts.next().unwrap_or("").trim().to_string();
+                match name.as_str() {
+                    "content-length" => content_length = value.parse().unwrap_or(0),
+                    "from" => from = Some(value),
+                    "authorization" => authorization = Some(value),
+                    _ => {}
+                }
+            }
+            Err(_) => return,
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let mut request_parts = request_line.split_whitespace();
+    let method = request_parts.next().unwrap_or("GET").to_string();
+    let target = request_parts.next().unwrap_or("/");
+    let (path, query) = match target.find('?') {
+        Some(idx) => (&target[..idx], &target[idx + 1..]),
+        None => (target, ""),
+    };
+
+    let token = authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Token token="))
+        .unwrap_or_default()
+        .to_string();
+
+    state.requests.lock().unwrap().push(RecordedRequest {
+        method: method.clone(),
+        path: path.to_string(),
+        query: query.to_string(),
+        from,
+        authorization,
+        body: body.clone(),
+    });
+
+    let failed = state
+        .failures
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok();
+
+    let not_found = (
+        "404 Not Found",
+        json!({"error": {"message": "Not Found", "code": 2100}}),
+    );
+
+    let (status, body) = if failed && state.failure_status.starts_with("429") {
+        (
+            state.failure_status,
+            json!({"error": {"message": "Rate Limit Exceeded", "code": 2020}}),
+        )
+    } else if failed {
+        (
+            state.failure_status,
+            json!({"error": {"message": state.failure_status, "code": 2000}}),
+        )
+    } else if token == REVOKED_TOKEN {
+        (
+            "401 Unauthorized",
+            json!({"error": {"message": "Authentication Required", "code": 2006}}),
+        )
+    } else if token == ACCOUNT_TOKEN && path == "/users/me" {
+        (
+            "400 Bad Request",
+            json!({"error": {"message": "Invalid Input Provided", "code": 2001}}),
+        )
+    } else if token == READ_ONLY_TOKEN && path == "/users/me" {
+        let mut me = state.fixtures["/users/me"].clone();
+        me["user"]["role"] = json!("read_only_user");
+        ("200 OK", me)
+    } else if joins_array_values(query) {
+        (
+            "400 Bad Request",
+            json!({"error": {"message": "Invalid Input Provided", "code": 2001}}),
+        )
+    } else if path.starts_with("/v2/") && method == "POST" {
+        ("202 Accepted", accept_event(path, &body))
+    } else if method == "GET" {
+        match state.fixtures.get(path) {
+            Some(fixture) if is_collection(path) => ("200 OK", make_page(path, fixture, query)),
+            Some(fixture) => ("200 OK", fixture.clone()),
+            None => match find_record(&state.fixtures, path) {
+                Some(record) => ("200 OK", record),
+                None => not_found,
+            },
+        }
+    } else {
+        match write_target_exists(&state.fixtures, path) {
+            Some(_) if method == "POST" && path == "/incidents" => {
+                ("201 Created", new_incident(body))
+            }
+            Some(_) if method == "POST" => ("201 Created", with_new_id(body)),
+            Some(true) => ("200 OK", body),
+            _ => not_found,
+        }
+    };
+
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nRetry-After: 0\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).ok();
+}
+
+/// PagerDuty wants `include[]=a&include[]=b`, a single `include[]=a,b` isn't split.
+fn joins_array_values(query: &str) -> bool {
+    query.split('&').any(|pair| {
+        let mut parts = pair.splitn(2, '=');
+        let name = parts.next().unwrap_or_default();
+        let value = parts.next().unwrap_or_default();
+        (name.ends_with("[]") || name.ends_with("%5B%5D"))
+            && (value.contains(',') || value.contains("%2C"))
+    })
+}
+
+/// `/abilities` is a plain list, it isn't paginated.
+fn is_collection(path: &str) -> bool {
+    !path.trim_start_matches('/').contains('/') && path != "/abilities"
+}
+
+/// The Events API only reports whether an event was queued, alerts also get a dedup key.
+fn accept_event(path: &str, body: &Value) -> Value {
+    let mut resp = json!({"status": "success", "message": "Event processed"});
+    if path == "/v2/enqueue" {
+        resp["dedup_key"] = match &body["dedup_key"] {
+            Value::Null => json!("generated-dedup-key"),
+            dedup_key => dedup_key.clone(),
+        };
+    }
+    resp
+}
+
+/// PagerDuty answers a create with the new record, including the ID it was given.
+fn with_new_id(mut body: Value) -> Value {
+    if let Some(record) = body
+        .as_object_mut()
+        .and_then(|wrapper| wrapper.values_mut().next())
+        .and_then(|record| record.as_object_mut())
+    {
+        record
+            .entry("id")
+            .or_insert_with(|| Value::String("PNEW001".into()));
+    }
+    body
+}
+
+/// An opened incident gets a number, a status and the escalation policy of its service.
+fn new_incident(body: Value) -> Value {
+    let mut body = with_new_id(body);
+    let incident = &mut body["incident"];
+    incident["incident_number"] = json!(1234);
+    incident["status"] = json!("triggered");
+    incident["created_at"] = json!("2020-04-01T12:00:00Z");
+    incident["html_url"] = json!("https://subdomain.pagerduty.com/incidents/PNEW001");
+    incident["escalation_policy"] = json!({"id": "P7DBLPX", "type": "escalation_policy_reference"});
+    body
+}
+
+/// `Some(true)` when the path points at `/{collection}/{id}...` for a known record,
+/// `Some(false)` when it points at a known collection itself.
+fn write_target_exists(fixtures: &BTreeMap<&str, Value>, path: &str) -> Option<bool> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    let collection = segments.next()?;
+    let fixture = fixtures.get(format!("/{}", collection).as_str())?;
+
+    match segments.next() {
+        None => Some(false),
+        Some(id) => {
+            let records = fixture[collection].as_array()?;
+            if records.iter().any(|record| record["id"] == id) {
+                Some(true)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// A `/{collection}/{id}` lookup, wrapped the way PagerDuty wraps a single record.
+fn find_record(fixtures: &BTreeMap<&str, Value>, path: &str) -> Option<Value> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    let collection = segments.next()?;
+    let id = segments.next()?;
+    if segments.next().is_some() {
+        return None;
+    }
+
+    let fixture = fixtures.get(format!("/{}", collection).as_str())?;
+    let record = fixture[collection]
+        .as_array()?
+        .iter()
+        .find(|record| record["id"] == id)?
+        .clone();
+
+    let mut body = json!({});
+    body[collection.trim_end_matches('s')] = record;
+    Some(body)
+}
+
+fn make_page(path: &str, fixture: &Value, query: &str) -> Value {
+    let key = path.trim_start_matches('/');
+
+    let mut offset = 0;
+    let mut limit = MOCK_PAGE_LIMIT;
+    let mut with_total = false;
+    let mut statuses = Vec::new();
+    let mut user_ids = Vec::new();
+    let mut team_ids = Vec::new();
+    for pair in query.split('&') {
+        let mut parts = pair.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("offset"), Some(value)) => offset = value.parse().unwrap_or(0),
+            (Some("limit"), Some(value)) => {
+                limit = value
+                    .parse()
+                    .unwrap_or(MOCK_PAGE_LIMIT)
+                    .min(MOCK_PAGE_LIMIT)
+            }
+            (Some("total"), Some("true")) => with_total = true,
+            (Some("statuses[]"), Some(value)) | (Some("statuses%5B%5D"), Some(value)) => {
+                statuses.push(Value::String(value.to_string()))
+            }
+            (Some("user_ids[]"), Some(value)) | (Some("user_ids%5B%5D"), Some(value)) => {
+                user_ids.push(Value::String(value.to_string()))
+            }
+            (Some("team_ids[]"), Some(value)) | (Some("team_ids%5B%5D"), Some(value)) => {
+                team_ids.push(Value::String(value.to_string()))
+            }
+            _ => {}
+        }
+    }
+
+    let records: Vec<Value> = fixture[key]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|record| statuses.is_empty() || statuses.contains(&record["status"]))
+        .filter(|record| user_ids.is_empty() || user_ids.contains(&record["user"]["id"]))
+        .filter(|record| {
+            team_ids.is_empty()
+                || record["teams"]
+                    .as_array()
+                    .map(|teams| teams.iter().any(|team| team_ids.contains(&team["id"])))
+                    .unwrap_or(false)
+        })
+        .collect();
+
+    let page: Vec<Value> = records.iter().skip(offset).take(limit).cloned().collect();
+    let more = offset + page.len() < records.len();
+
+    let mut body = json!({
+        "limit": limit,
+        "offset": offset,
+        "more": more,
+        "total": if with_total { json!(records.len()) } else { Value::Null },
+    });
+    body[key] = Value::Array(page);
+    body
+}